@@ -1,3 +1,45 @@
+use std::path::Path;
+
+/// Tray icon the health variants are drawn on
+const TRAY_ICON: &str = "icons/tray-icon-rounded.png";
+
+/// Dot colors, indexed like `tray::Health` (Good, Warn, Critical)
+const HEALTH_COLORS: [(&str, [u8; 4]); 3] = [
+  ("good", [52, 199, 89, 255]),
+  ("warn", [255, 204, 0, 255]),
+  ("critical", [255, 59, 48, 255]),
+];
+
+/// Draw the tray icon with a green/yellow/red dot in the bottom-right corner
+/// for each health level, embedded by tray.rs
+fn generate_health_icons() {
+  println!("cargo:rerun-if-changed={}", TRAY_ICON);
+  let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+  let base = image::open(TRAY_ICON).expect("failed to load tray icon").to_rgba8();
+  let (width, height) = base.dimensions();
+
+  let radius = (width.min(height) / 5).max(2) as i64;
+  let (cx, cy) = (width as i64 - radius - 1, height as i64 - radius - 1);
+
+  let mut icons = Vec::with_capacity(HEALTH_COLORS.len());
+  for (name, color) in HEALTH_COLORS {
+    let mut icon = base.clone();
+    for (x, y, pixel) in icon.enumerate_pixels_mut() {
+      let (dx, dy) = (x as i64 - cx, y as i64 - cy);
+      if dx * dx + dy * dy <= radius * radius {
+        pixel.0 = color;
+      }
+    }
+    let path = Path::new(&out_dir).join(format!("tray-health-{}.png", name));
+    icon.save(&path).expect("failed to write health icon");
+    icons.push(format!("include_bytes!({:?})", path.display().to_string()));
+  }
+
+  let source = format!("static HEALTH_ICONS: [&[u8]; 3] = [{}];\n", icons.join(", "));
+  std::fs::write(Path::new(&out_dir).join("health_icons.rs"), source).expect("failed to write health_icons.rs");
+}
+
 fn main() {
+  generate_health_icons();
   tauri_build::build()
 }
@@ -1,58 +1,94 @@
 mod modules;
 
-use std::sync::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Instant;
 use tauri::Manager;
-use tauri_plugin_global_shortcut::{Code, Modifiers, Shortcut, ShortcutState, GlobalShortcutExt};
+use tauri_plugin_global_shortcut::{ShortcutState, GlobalShortcutExt};
 use tauri::tray::{TrayIconBuilder, MouseButton, MouseButtonState};
-use tauri::menu::{MenuBuilder, MenuItemBuilder};
+use tauri::menu::{CheckMenuItemBuilder, MenuBuilder, MenuItemBuilder};
+use tauri_plugin_autostart::MacosLauncher;
 
-use modules::AppState;
-use modules::system::{get_system_stats, start_tray_update_loop};
-use modules::upload::{get_clipboard_image, upload_image, handle_upload_shortcut};
-use modules::git::{get_git_branches, switch_git_branch};
-use modules::config::get_mijia_config;
-use modules::mijia::{execute_device_action, get_device_prop, set_device_prop, get_playback_state};
-
-// Native imports
-use objc2::MainThreadMarker;
-use objc2_app_kit::{NSStatusBar, NSVariableStatusItemLength};
-use objc2_foundation::ns_string;
+use modules::{AppState, TrayLabel, TrayStatsItems};
+use modules::system::{get_system_stats, get_login_items, get_top_gpu_processes, get_zombie_processes, get_locale, get_disk_usage, get_system_load, get_fan_speeds, get_process_network, get_network_interfaces, get_memory_breakdown, get_stats_history, start_tray_update_loop};
+use modules::upload::{capture_and_upload, handle_capture_shortcut, get_clipboard_image, get_clipboard_text, upload_image, upload_file, list_upload_backends, handle_upload_shortcut, copy_last_upload_url, benchmark_clipboard, cancel_upload, test_upload_config, upload_dropped_files};
+use modules::git::{get_git_branches, switch_git_branch, get_git_status, get_git_log, get_git_diff, git_pull, git_push, create_git_branch, git_stash_save, git_stash_list, git_stash_pop};
+use modules::config::{load_config, get_mijia_config, save_config, validate_config};
+use modules::shortcuts::{parse_shortcut_or_default, DEFAULT_CAPTURE_SHORTCUT, DEFAULT_TOGGLE_WINDOW_SHORTCUT, DEFAULT_UPLOAD_SHORTCUT};
+use modules::watcher::start_config_watcher;
+use modules::metrics::start_metrics_server;
+use modules::logging::{build_logger, get_log_path, reveal_logs};
+use modules::diagnostics::get_diagnostics;
+use modules::window_state::{save_window_state, show_main_window, toggle_main_window};
+use modules::autostart::{get_autostart, set_autostart, sync_autostart, toggle_autostart};
+use modules::tray::{effective_display_mode, set_tray_label, get_tray_label, set_monitoring_paused, toggle_monitoring_paused, setup_status_item, update_copy_url_item, TRAY_ICON_ID};
+use modules::history::{get_upload_history, clear_upload_history};
+use modules::data_usage::{get_daily_data_usage, load_data_usage};
+use modules::mijia::{execute_device_action, get_device_prop, set_device_prop, get_playback_state, list_devices, list_mijia_devices, reload_mijia_config, speak_text, get_volume, set_volume, playback_play, playback_pause, playback_next, playback_prev, start_playback_poller};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let config = load_config();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_autostart::init(MacosLauncher::LaunchAgent, None))
         .manage(AppState {
             sys: Mutex::new(sysinfo::System::new_all()),
             networks: Mutex::new(sysinfo::Networks::new_with_refreshed_list()),
+            last_network_refresh: Mutex::new(Instant::now()),
+            last_disk_io: Mutex::new(None),
+            disks: Mutex::new(sysinfo::Disks::new_with_refreshed_list()),
+            disk_mount: Mutex::new(config.disk.mount_point.clone()),
+            last_interface_sample: Mutex::new(None),
+            #[cfg(target_os = "macos")]
             status_item: Mutex::new(None),
+            data_usage: Mutex::new(load_data_usage()),
+            tray_label: Mutex::new(TrayLabel {
+                prefix: config.tray.prefix.clone(),
+                suffix: config.tray.suffix.clone(),
+            }),
+            display_mode: Mutex::new(effective_display_mode(config.tray.display_mode)),
+            config_reloaded: AtomicBool::new(false),
+            upload_cancels: Arc::new(Mutex::new(HashMap::new())),
+            uploads_in_flight: Arc::new(AtomicUsize::new(0)),
+            // tauri.conf.json creates the window visible
+            window_visible: AtomicBool::new(true),
+            monitoring_paused: AtomicBool::new(false),
+            latest_stats: Mutex::new(None),
+            stats_history: Mutex::new(VecDeque::with_capacity(modules::system::STATS_HISTORY_LEN)),
+            autostart_item: Mutex::new(None),
+            pause_item: Mutex::new(None),
+            stats_items: Mutex::new(None),
+            copy_url_item: Mutex::new(None),
         })
-        .on_window_event(|window, event| match event {
-            tauri::WindowEvent::CloseRequested { api, .. } => {
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if let Some(webview_window) = window.app_handle().get_webview_window(window.label()) {
+                    save_window_state(&webview_window);
+                }
                 let _ = window.hide();
+                window.state::<AppState>().window_visible.store(false, Ordering::Relaxed);
                 api.prevent_close();
             }
-            _ => {}
         })
-        .setup(|app| {
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
+        .setup(move |app| {
+            app.handle().plugin(build_logger(&config.general))?;
 
+            #[cfg(target_os = "macos")]
             app.set_activation_policy(tauri::ActivationPolicy::Accessory);
 
-            // Register global shortcut for image upload (Shift+Cmd+U)
-            log::info!("Registering global shortcut: Shift+Cmd+U for image upload");
+            // Register global shortcut for image upload (default Shift+Cmd+U)
+            let upload_shortcut = parse_shortcut_or_default(&config.shortcuts.upload, DEFAULT_UPLOAD_SHORTCUT);
+            log::info!("Registering global shortcut: {} for image upload", upload_shortcut.into_string());
 
             let handle = app.handle().clone();
             app.global_shortcut().on_shortcut(
-                Shortcut::new(Some(Modifiers::SHIFT | Modifiers::SUPER), Code::KeyU),
+                upload_shortcut,
                 move |_app, _shortcut, event| {
                     if event.state == ShortcutState::Pressed {
                         handle_upload_shortcut(handle.clone());
@@ -60,29 +96,93 @@ pub fn run() {
                 }
             )?;
 
+            // Show/hide the window (default Shift+Cmd+P)
+            let toggle_shortcut = parse_shortcut_or_default(&config.shortcuts.toggle_window, DEFAULT_TOGGLE_WINDOW_SHORTCUT);
+            log::info!("Registering global shortcut: {} for window toggle", toggle_shortcut.into_string());
+
+            let handle = app.handle().clone();
+            if let Err(e) = app.global_shortcut().on_shortcut(
+                toggle_shortcut,
+                move |_app, _shortcut, event| {
+                    if event.state == ShortcutState::Pressed {
+                        toggle_main_window(&handle);
+                    }
+                }
+            ) {
+                log::error!("Failed to register window toggle shortcut: {}", e);
+            }
+
+            // Screenshot and upload (default Shift+Cmd+X)
+            let capture_shortcut = parse_shortcut_or_default(&config.shortcuts.capture, DEFAULT_CAPTURE_SHORTCUT);
+            log::info!("Registering global shortcut: {} for screenshot upload", capture_shortcut.into_string());
+
+            let handle = app.handle().clone();
+            if let Err(e) = app.global_shortcut().on_shortcut(
+                capture_shortcut,
+                move |_app, _shortcut, event| {
+                    if event.state == ShortcutState::Pressed {
+                        handle_capture_shortcut(handle.clone());
+                    }
+                }
+            ) {
+                log::error!("Failed to register screenshot shortcut: {}", e);
+            }
+
             // Setup tray
+            let cpu_item = MenuItemBuilder::with_id("stats_cpu", "CPU: --").enabled(false).build(app)?;
+            let memory_item = MenuItemBuilder::with_id("stats_memory", "Memory: --").enabled(false).build(app)?;
+            let network_item = MenuItemBuilder::with_id("stats_network", "Network: --").enabled(false).build(app)?;
             let show_item = MenuItemBuilder::with_id("show", "Show Window").build(app)?;
+            let copy_url_item = MenuItemBuilder::with_id("copy_last_url", "Copy Last URL").build(app)?;
+            let pause_item = CheckMenuItemBuilder::with_id("pause_monitoring", "Pause Monitoring").build(app)?;
+            let autostart_item = CheckMenuItemBuilder::with_id("autostart", "Launch at Login").build(app)?;
+            let reveal_logs_item = MenuItemBuilder::with_id("reveal_logs", "Reveal Logs").build(app)?;
             let quit_item = MenuItemBuilder::with_id("quit", "Quit").build(app)?;
             let tray_menu = MenuBuilder::new(app)
+                .item(&cpu_item)
+                .item(&memory_item)
+                .item(&network_item)
+                .separator()
                 .item(&show_item)
+                .item(&copy_url_item)
+                .item(&pause_item)
+                .item(&autostart_item)
+                .item(&reveal_logs_item)
                 .separator()
                 .item(&quit_item)
                 .build()?;
+            *app.state::<AppState>().autostart_item.lock().unwrap() = Some(autostart_item);
+            *app.state::<AppState>().pause_item.lock().unwrap() = Some(pause_item);
+            *app.state::<AppState>().stats_items.lock().unwrap() = Some(TrayStatsItems {
+                cpu: cpu_item,
+                memory: memory_item,
+                network: network_item,
+            });
+            *app.state::<AppState>().copy_url_item.lock().unwrap() = Some(copy_url_item);
+            sync_autostart(app.handle(), &config);
+            update_copy_url_item(app.handle());
 
-            let icon = tauri::image::Image::from_bytes(include_bytes!("../icons/tray-icon-rounded.png"))
-                .expect("Failed to load tray icon");
+            let mut tray_builder = TrayIconBuilder::with_id(TRAY_ICON_ID);
+            match tauri::image::Image::from_bytes(include_bytes!("../icons/tray-icon-rounded.png")) {
+                Ok(icon) => tray_builder = tray_builder.icon(icon),
+                Err(e) => log::error!("Failed to load tray icon: {}", e),
+            }
 
-            let _tray = TrayIconBuilder::new()
-                .icon(icon)
+            let tray = tray_builder
                 .tooltip("System Monitor")
                 .menu(&tray_menu)
                 .show_menu_on_left_click(false)
                 .on_menu_event(|app, event| {
                     match event.id().as_ref() {
                         "show" => {
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.show();
-                                let _ = window.set_focus();
+                            show_main_window(app);
+                        }
+                        "copy_last_url" => copy_last_upload_url(),
+                        "pause_monitoring" => toggle_monitoring_paused(app),
+                        "autostart" => toggle_autostart(app),
+                        "reveal_logs" => {
+                            if let Err(e) = reveal_logs() {
+                                log::error!("{}", e);
                             }
                         }
                         "quit" => {
@@ -93,42 +193,84 @@ pub fn run() {
                 })
                 .on_tray_icon_event(|tray, event| {
                     if let tauri::tray::TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
-                        let app = tray.app_handle();
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
+                        show_main_window(tray.app_handle());
                     }
                 })
-                .build(app)?;
-
-            // Setup native status bar
-            let mtm = unsafe { MainThreadMarker::new_unchecked() };
-            let status_bar = NSStatusBar::systemStatusBar();
-            let status_item = status_bar.statusItemWithLength(NSVariableStatusItemLength);
-
-            if let Some(button) = status_item.button(mtm) {
-                 button.setTitle(ns_string!("System Monitor"));
+                .build(app);
+            if let Err(e) = tray {
+                log::error!("Failed to create tray icon: {}", e);
             }
 
-            let state = app.state::<AppState>();
-            *state.status_item.lock().unwrap() = Some(modules::ThreadSafeStatusItem(status_item));
+            setup_status_item(app.handle());
 
             start_tray_update_loop(app.handle().clone());
+            start_config_watcher(app.handle().clone());
+            start_metrics_server(app.handle().clone(), &config.metrics);
+            start_playback_poller(app.handle().clone(), &config.mijia);
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             get_system_stats,
+            get_login_items,
+            get_top_gpu_processes,
+            get_zombie_processes,
+            get_locale,
+            get_disk_usage,
+            get_system_load,
+            get_fan_speeds,
+            get_process_network,
+            get_network_interfaces,
+            get_memory_breakdown,
+            get_stats_history,
+            get_daily_data_usage,
+            set_tray_label,
+            get_tray_label,
+            set_monitoring_paused,
             get_clipboard_image,
+            get_clipboard_text,
             upload_image,
+            upload_file,
+            capture_and_upload,
+            cancel_upload,
+            test_upload_config,
+            upload_dropped_files,
+            list_upload_backends,
+            get_upload_history,
+            clear_upload_history,
+            benchmark_clipboard,
             get_git_branches,
             switch_git_branch,
+            get_git_status,
+            get_git_log,
+            get_git_diff,
+            git_pull,
+            git_push,
+            create_git_branch,
+            git_stash_save,
+            git_stash_list,
+            git_stash_pop,
             get_mijia_config,
+            save_config,
+            validate_config,
+            get_log_path,
+            get_diagnostics,
+            get_autostart,
+            set_autostart,
             execute_device_action,
             get_device_prop,
             set_device_prop,
-            get_playback_state
+            get_playback_state,
+            list_devices,
+            list_mijia_devices,
+            reload_mijia_config,
+            speak_text,
+            get_volume,
+            set_volume,
+            playback_play,
+            playback_pause,
+            playback_next,
+            playback_prev
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
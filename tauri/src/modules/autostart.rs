@@ -0,0 +1,63 @@
+use tauri::{AppHandle, Manager};
+use tauri_plugin_autostart::ManagerExt;
+use crate::modules::AppState;
+use crate::modules::config::{update_config, Config};
+use crate::modules::error::PulseError;
+use crate::modules::utils::LockExt;
+
+/// Whether Pulse is registered to launch at login
+#[tauri::command]
+pub fn get_autostart(app: AppHandle) -> Result<bool, PulseError> {
+    app.autolaunch()
+        .is_enabled()
+        .map_err(|e| PulseError::Io(format!("Failed to read launch-at-login state: {}", e)))
+}
+
+/// Register or unregister launch at login and remember the choice in config.toml.
+/// Returns the resulting state; the tray checkmark follows it even on failure.
+#[tauri::command]
+pub fn set_autostart(app: AppHandle, enabled: bool) -> Result<bool, PulseError> {
+    let result = apply_autostart(&app, enabled);
+    if result.is_ok() {
+        if let Err(e) = update_config(|config| config.general.autostart = Some(enabled)) {
+            log::warn!("Failed to save autostart setting: {}", e);
+        }
+    }
+    sync_menu_check(&app);
+    result.map(|_| enabled)
+}
+
+fn apply_autostart(app: &AppHandle, enabled: bool) -> Result<(), PulseError> {
+    let autolaunch = app.autolaunch();
+    let result = if enabled { autolaunch.enable() } else { autolaunch.disable() };
+    result.map_err(|e| {
+        log::error!("Failed to {} launch at login: {}", if enabled { "enable" } else { "disable" }, e);
+        PulseError::Io(format!("Failed to update launch at login (check System Settings > Login Items): {}", e))
+    })
+}
+
+/// Toggle from the tray menu item
+pub fn toggle_autostart(app: &AppHandle) {
+    let enabled = get_autostart(app.clone()).unwrap_or(false);
+    let _ = set_autostart(app.clone(), !enabled);
+}
+
+/// Set the tray checkmark from the system state
+fn sync_menu_check(app: &AppHandle) {
+    let enabled = get_autostart(app.clone()).unwrap_or(false);
+    if let Some(item) = app.state::<AppState>().autostart_item.lock_or_recover().as_ref() {
+        let _ = item.set_checked(enabled);
+    }
+}
+
+/// Re-apply `general.autostart` at launch, e.g. after the login item was
+/// removed in System Settings or the app was reinstalled
+pub fn sync_autostart(app: &AppHandle, config: &Config) {
+    if let Some(enabled) = config.general.autostart {
+        if get_autostart(app.clone()).ok() != Some(enabled) {
+            log::info!("Restoring launch at login: {}", enabled);
+            let _ = apply_autostart(app, enabled);
+        }
+    }
+    sync_menu_check(app);
+}
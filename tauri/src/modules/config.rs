@@ -1,21 +1,322 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
+use crate::modules::error::PulseError;
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default)]
 pub struct Config {
     #[serde(default)]
     pub upload: UploadConfig,
     #[serde(default)]
     pub mijia: MijiaConfig,
+    #[serde(default)]
+    pub tray: TrayConfig,
+    #[serde(default)]
+    pub general: GeneralConfig,
+    #[serde(default)]
+    pub shortcuts: ShortcutsConfig,
+    #[serde(default)]
+    pub git: GitConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub disk: DiskConfig,
+}
+
+/// Volume whose usage is reported in stats
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DiskConfig {
+    /// Mount point, e.g. "/Volumes/Data"; falls back to / when not mounted
+    pub mount_point: String,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        Self {
+            mount_point: "/".to_string(),
+        }
+    }
+}
+
+/// Prometheus-style `/metrics` endpoint; read at launch
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// Listen address; loopback only unless changed on purpose
+    pub address: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:9187".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct GitConfig {
+    /// Repository for the git panel; defaults to ~/.claude
+    #[serde(default)]
+    pub repo_path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ShortcutsConfig {
+    /// Global shortcut for clipboard upload, e.g. "Shift+Cmd+U"
+    pub upload: String,
+    /// Global shortcut that shows or hides the main window
+    pub toggle_window: String,
+    /// Global shortcut for an interactive screenshot that is uploaded
+    pub capture: String,
+}
+
+impl Default for ShortcutsConfig {
+    fn default() -> Self {
+        Self {
+            upload: super::shortcuts::DEFAULT_UPLOAD_SHORTCUT.to_string(),
+            toggle_window: super::shortcuts::DEFAULT_TOGGLE_WINDOW_SHORTCUT.to_string(),
+            capture: super::shortcuts::DEFAULT_CAPTURE_SHORTCUT.to_string(),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct GeneralConfig {
+    /// Daily network usage cap in GB; no warning when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_data_cap_gb: Option<f64>,
+    /// Format numbers with the macOS locale's decimal separator (e.g. "1,2 M/s")
+    #[serde(default)]
+    pub locale_format: bool,
+    /// Push `system-stats` events to the window while it's visible
+    #[serde(default)]
+    pub emit_stats: bool,
+    /// Launch at login; re-applied at startup. Unset leaves the system setting alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autostart: Option<bool>,
+    /// Byte unit style for sizes and speeds
+    #[serde(default)]
+    pub units: UnitStyle,
+    /// Proxy for upload and Mijia requests ("http://", "https://" or "socks5://").
+    /// Empty falls back to the HTTPS_PROXY / HTTP_PROXY environment variables.
+    #[serde(default)]
+    pub proxy: String,
+    /// Always open the window at its default position instead of where it was last hidden
+    #[serde(default)]
+    pub fixed_window_position: bool,
+    /// Log level for the files under logs/: "error", "warn", "info" (default), "debug" or "trace"
+    #[serde(default)]
+    pub log_level: String,
+}
+
+impl GeneralConfig {
+    /// Parsed `log_level`, falling back to info when unset or unknown
+    pub fn log_level(&self) -> log::LevelFilter {
+        self.log_level.parse().unwrap_or(log::LevelFilter::Info)
+    }
+
+    /// Configured proxy, or `None` to let reqwest pick it up from the environment
+    pub fn proxy(&self) -> Result<Option<reqwest::Proxy>, PulseError> {
+        let url = self.proxy.trim();
+        if url.is_empty() {
+            return Ok(None);
+        }
+        reqwest::Proxy::all(url)
+            .map(Some)
+            .map_err(|e| PulseError::Config(format!("Invalid proxy URL '{}': {}", url, e)))
+    }
+}
+
+/// Byte unit style: compact 1024-based letters ("K/s"), IEC binary ("KiB/s")
+/// or SI decimal ("kB/s")
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitStyle {
+    #[default]
+    Compact,
+    Binary,
+    Decimal,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct UploadConfig {
+    #[serde(default)]
     pub url: String,
+    #[serde(default)]
     pub token: String,
     #[serde(default)]
     pub base_url: String,
+    #[serde(default)]
+    pub result_verbosity: ResultVerbosity,
+    #[serde(default = "default_max_file_size_mb")]
+    pub max_file_size_mb: u64,
+    #[serde(default)]
+    pub format: UploadFormat,
+    /// JPEG quality (1-100)
+    #[serde(default = "default_quality")]
+    pub quality: u8,
+    /// Maximum number of upload history entries kept
+    #[serde(default = "default_history_limit")]
+    pub history_limit: usize,
+    /// Write the uploaded URL back to the clipboard on success
+    #[serde(default = "default_true")]
+    pub copy_url_to_clipboard: bool,
+    /// Recompress PNG uploads with oxipng; smaller files but slower
+    #[serde(default)]
+    pub optimize: bool,
+    /// oxipng preset, 0 (fast) to 6 (smallest)
+    #[serde(default = "default_optimize_level")]
+    pub optimize_level: u8,
+    /// Request timeout per upload attempt, in seconds
+    #[serde(default = "default_upload_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Retries after a 5xx/429 response or network error, with exponential backoff
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Return the earlier URL instead of uploading identical content again
+    #[serde(default = "default_true")]
+    pub dedup: bool,
+    /// Show a native notification when a shortcut upload finishes
+    #[serde(default = "default_true")]
+    pub notify: bool,
+    /// PEM file with an extra root certificate, e.g. for a server behind an internal CA
+    #[serde(default)]
+    pub ca_cert_path: String,
+    /// Skip TLS certificate verification entirely. Insecure; only for testing.
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+    /// HTTP method for the upload request
+    #[serde(default)]
+    pub method: UploadMethod,
+    /// Multipart form field carrying the file
+    #[serde(default = "default_field_name")]
+    pub field_name: String,
+    /// Dotted path to the image URL in the response JSON, e.g. "data.link"
+    #[serde(default = "default_response_url_path")]
+    pub response_url_path: String,
+    /// Dotted path to the stored filename in the response JSON
+    #[serde(default = "default_response_filename_path")]
+    pub response_filename_path: String,
+    /// Extra request headers, e.g. `{ "X-Api-Key" = "..." }`. Applied after
+    /// the bearer token, so an "Authorization" entry replaces it.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Named upload hosts (`[[upload.backends]]`)
+    #[serde(default)]
+    pub backends: Vec<UploadBackend>,
+    /// Backend used when no name is given; falls back to the top-level url/token
+    #[serde(default)]
+    pub default_backend: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct UploadBackend {
+    pub name: String,
+    pub url: String,
+    pub token: String,
+    #[serde(default)]
+    pub base_url: String,
+}
+
+impl UploadConfig {
+    /// oxipng level when `optimize` is enabled
+    pub fn optimize_level(&self) -> Option<u8> {
+        self.optimize.then_some(self.optimize_level.min(6))
+    }
+
+    /// Root certificate from `ca_cert_path`. An unreadable or invalid file is
+    /// an error rather than a silent fallback to the system roots.
+    pub fn ca_certificate(&self) -> Result<Option<reqwest::Certificate>, PulseError> {
+        let path = self.ca_cert_path.trim();
+        if path.is_empty() {
+            return Ok(None);
+        }
+        let pem = fs::read(super::utils::expand_home(path))
+            .map_err(|e| PulseError::Io(format!("Failed to read CA certificate '{}': {}", path, e)))?;
+        reqwest::Certificate::from_pem(&pem)
+            .map(Some)
+            .map_err(|e| PulseError::Config(format!("Invalid CA certificate '{}': {}", path, e)))
+    }
+
+    /// Resolve the backend to upload to: the named one, else `default_backend`,
+    /// else the top-level url/token, else the first configured backend
+    pub fn resolve_backend(&self, name: Option<&str>) -> Result<UploadBackend, PulseError> {
+        let name = name.filter(|n| !n.is_empty()).or_else(|| {
+            Some(self.default_backend.as_str()).filter(|n| !n.is_empty())
+        });
+
+        if let Some(name) = name {
+            return self.backends.iter().find(|b| b.name == name).cloned().ok_or_else(|| {
+                let valid: Vec<&str> = self.backends.iter().map(|b| b.name.as_str()).collect();
+                PulseError::Config(format!("Unknown upload backend '{}'. Valid backends: {}", name, valid.join(", ")))
+            });
+        }
+
+        if !self.url.is_empty() && (!self.token.is_empty() || !self.headers.is_empty()) {
+            return Ok(UploadBackend {
+                name: "default".to_string(),
+                url: self.url.clone(),
+                token: self.token.clone(),
+                base_url: self.base_url.clone(),
+            });
+        }
+
+        self.backends
+            .first()
+            .cloned()
+            .ok_or_else(|| PulseError::Config("Upload not configured. Please edit ~/.config/pulse/config.toml".to_string()))
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_history_limit() -> usize {
+    100
+}
+
+fn default_max_file_size_mb() -> u64 {
+    100
+}
+
+fn default_optimize_level() -> u8 {
+    2
+}
+
+fn default_response_url_path() -> String {
+    "url".to_string()
+}
+
+fn default_response_filename_path() -> String {
+    "originalFileName".to_string()
+}
+
+fn default_field_name() -> String {
+    "file".to_string()
+}
+
+fn default_upload_timeout_secs() -> u64 {
+    60
+}
+
+fn default_playback_poll_secs() -> u64 {
+    5
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_quality() -> u8 {
+    85
 }
 
 impl Default for UploadConfig {
@@ -24,14 +325,100 @@ impl Default for UploadConfig {
             url: String::new(),
             token: String::new(),
             base_url: String::new(),
+            result_verbosity: ResultVerbosity::default(),
+            max_file_size_mb: default_max_file_size_mb(),
+            format: UploadFormat::default(),
+            quality: default_quality(),
+            history_limit: default_history_limit(),
+            copy_url_to_clipboard: true,
+            optimize: false,
+            optimize_level: default_optimize_level(),
+            timeout_secs: default_upload_timeout_secs(),
+            max_retries: default_max_retries(),
+            notify: true,
+            dedup: true,
+            method: UploadMethod::default(),
+            field_name: default_field_name(),
+            response_url_path: default_response_url_path(),
+            response_filename_path: default_response_filename_path(),
+            headers: HashMap::new(),
+            ca_cert_path: String::new(),
+            danger_accept_invalid_certs: false,
+            backends: Vec::new(),
+            default_backend: String::new(),
+        }
+    }
+}
+
+/// HTTP method used to send uploads: "PUT" (default) or "POST"
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum UploadMethod {
+    #[default]
+    #[serde(alias = "put")]
+    Put,
+    #[serde(alias = "post")]
+    Post,
+}
+
+impl UploadMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UploadMethod::Put => "PUT",
+            UploadMethod::Post => "POST",
+        }
+    }
+}
+
+/// Encoding used for clipboard image uploads
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadFormat {
+    #[default]
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl UploadFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            UploadFormat::Png => "png",
+            UploadFormat::Jpeg => "jpg",
+            UploadFormat::Webp => "webp",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            UploadFormat::Png => "image/png",
+            UploadFormat::Jpeg => "image/jpeg",
+            UploadFormat::Webp => "image/webp",
         }
     }
 }
 
+/// Shape of the `upload-result` event payload
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultVerbosity {
+    #[default]
+    Full,
+    Minimal,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct MijiaConfig {
     pub api_base: String,
     pub api_key: String,
+    /// Device used when a command is called without a did
+    #[serde(default)]
+    pub default_did: String,
+    /// Poll default_did's playback state and emit `mijia-playback` on changes; read at launch
+    #[serde(default)]
+    pub poll_playback: bool,
+    #[serde(default = "default_playback_poll_secs")]
+    pub playback_poll_secs: u64,
 }
 
 impl Default for MijiaConfig {
@@ -39,10 +426,191 @@ impl Default for MijiaConfig {
         Self {
             api_base: String::new(),
             api_key: String::new(),
+            default_did: String::new(),
+            poll_playback: false,
+            playback_poll_secs: default_playback_poll_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TrayConfig {
+    pub network: NetworkColorConfig,
+    pub memory: MemoryColorConfig,
+    /// Menu-bar template for the `all` display mode.
+    /// Tokens: {spark} {cpu} {up} {down} {mem} {icon}; other text is shown as-is.
+    pub format: String,
+    /// Render {icon} as a colored emoji dot; when off, a plain "●" tinted like the CPU text
+    pub emoji_icon: bool,
+    /// Tray refresh interval in milliseconds (0 or missing = 1000)
+    pub refresh_interval_ms: u64,
+    /// EMA smoothing factor for network speeds (1.0 = no smoothing)
+    pub smoothing_alpha: f64,
+    /// Uncolored text before the metrics, e.g. "🖥 "
+    pub prefix: String,
+    /// Uncolored text after the metrics
+    pub suffix: String,
+    /// Metrics shown in the menu bar at startup; clicking the text cycles through
+    /// them and the last pick is remembered in tray_state.json
+    pub display_mode: DisplayMode,
+    /// Menu-bar font size in points (0 = system default)
+    pub font_size: f64,
+    /// Interfaces counted in the tray network speeds
+    pub interface_filter: InterfaceFilter,
+    /// CPU temperature (°C) at which the CPU text turns red regardless of usage; 0 disables
+    pub cpu_temp_critical: f32,
+    /// Add a green/yellow/red status dot to the tray icon from CPU, memory and temperature
+    pub health_icon: bool,
+}
+
+/// Which metrics the menu-bar text shows
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayMode {
+    /// CPU and network together
+    #[default]
+    All,
+    Cpu,
+    Network,
+    Memory,
+}
+
+impl DisplayMode {
+    /// Next mode in click order, wrapping back to `All`
+    #[cfg(target_os = "macos")]
+    pub fn next(self) -> Self {
+        match self {
+            DisplayMode::All => DisplayMode::Cpu,
+            DisplayMode::Cpu => DisplayMode::Network,
+            DisplayMode::Network => DisplayMode::Memory,
+            DisplayMode::Memory => DisplayMode::All,
         }
     }
 }
 
+impl Default for TrayConfig {
+    fn default() -> Self {
+        Self {
+            network: NetworkColorConfig::default(),
+            memory: MemoryColorConfig::default(),
+            format: DEFAULT_TRAY_FORMAT.to_string(),
+            emoji_icon: true,
+            refresh_interval_ms: 0,
+            smoothing_alpha: 0.5,
+            prefix: String::new(),
+            suffix: String::new(),
+            display_mode: DisplayMode::default(),
+            font_size: 0.0,
+            interface_filter: InterfaceFilter::default(),
+            cpu_temp_critical: 90.0,
+            health_icon: false,
+        }
+    }
+}
+
+/// Default `tray.format`: CPU, upload and download speeds. Add `{spark}` for
+/// a CPU history sparkline.
+pub const DEFAULT_TRAY_FORMAT: &str = "{cpu},{up},{down}";
+
+const DEFAULT_REFRESH_INTERVAL_MS: u64 = 1000;
+const MIN_REFRESH_INTERVAL_MS: u64 = 250;
+
+impl TrayConfig {
+    /// Refresh interval for the tray loop.
+    /// Zero or missing falls back to 1000ms; anything lower is clamped to 250ms
+    /// so the sampling loop can't peg the CPU.
+    pub fn refresh_interval(&self) -> Duration {
+        let ms = match self.refresh_interval_ms {
+            0 => DEFAULT_REFRESH_INTERVAL_MS,
+            ms => ms.max(MIN_REFRESH_INTERVAL_MS),
+        };
+        Duration::from_millis(ms)
+    }
+
+    /// Template for the `all` display mode; empty falls back to the default
+    pub fn format_template(&self) -> &str {
+        if self.format.trim().is_empty() {
+            DEFAULT_TRAY_FORMAT
+        } else {
+            &self.format
+        }
+    }
+
+    /// Smoothing factor clamped to (0, 1]
+    pub fn smoothing_alpha(&self) -> f64 {
+        if self.smoothing_alpha > 0.0 && self.smoothing_alpha <= 1.0 {
+            self.smoothing_alpha
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Network speed color thresholds in MB/s
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct NetworkColorConfig {
+    pub warn_mbps: f64,
+    pub critical_mbps: f64,
+    /// Color the network speeds red, whatever their rate, once today's usage
+    /// is over `general.daily_data_cap_gb`
+    pub tint_over_cap: bool,
+}
+
+impl Default for NetworkColorConfig {
+    fn default() -> Self {
+        Self {
+            warn_mbps: 5.0,
+            critical_mbps: 10.0,
+            tint_over_cap: false,
+        }
+    }
+}
+
+/// Memory usage color thresholds in percent
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct MemoryColorConfig {
+    pub warn_percent: f32,
+    pub critical_percent: f32,
+}
+
+impl Default for MemoryColorConfig {
+    fn default() -> Self {
+        Self {
+            warn_percent: 70.0,
+            critical_percent: 90.0,
+        }
+    }
+}
+
+/// Interface name filter for the tray network totals. Names are matched as
+/// prefixes, so "utun" covers utun0, utun1, ... An empty `allow` list allows
+/// everything; `deny` wins over `allow`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct InterfaceFilter {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+impl Default for InterfaceFilter {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: vec!["lo".to_string()],
+        }
+    }
+}
+
+impl InterfaceFilter {
+    pub fn includes(&self, name: &str) -> bool {
+        let matches = |prefix: &String| name.starts_with(prefix.as_str());
+        (self.allow.is_empty() || self.allow.iter().any(matches)) && !self.deny.iter().any(matches)
+    }
+}
+
 /// Get config file path: ~/.config/pulse/config.toml (preferred) or ~/Library/Application Support/pulse/config.toml
 pub fn get_config_path() -> PathBuf {
     // Prefer ~/.config/pulse/config.toml (Unix-style)
@@ -60,6 +628,14 @@ pub fn get_config_path() -> PathBuf {
         .join("config.toml")
 }
 
+/// Directory holding config.toml and other persisted app data
+pub fn get_config_dir() -> PathBuf {
+    get_config_path()
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
 /// Load config from file
 pub fn load_config() -> Config {
     let config_path = get_config_path();
@@ -89,6 +665,223 @@ pub fn load_config() -> Config {
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct ConfigIssue {
+    pub field: String,
+    pub message: String,
+}
+
+/// Config problems split by severity: `errors` break a feature, `warnings` are
+/// tolerated (e.g. values that get clamped or fall back to defaults)
+#[derive(Debug, Serialize, Default)]
+pub struct ConfigValidation {
+    pub path: String,
+    pub errors: Vec<ConfigIssue>,
+    pub warnings: Vec<ConfigIssue>,
+}
+
+impl ConfigValidation {
+    fn error(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push(ConfigIssue { field: field.to_string(), message: message.into() });
+    }
+
+    fn warning(&mut self, field: &str, message: impl Into<String>) {
+        self.warnings.push(ConfigIssue { field: field.to_string(), message: message.into() });
+    }
+
+    fn check_url(&mut self, field: &str, url: &str) {
+        match reqwest::Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+            Ok(parsed) => self.error(field, format!("Unsupported URL scheme '{}', expected http or https", parsed.scheme())),
+            Err(e) => self.error(field, format!("Invalid URL '{}': {}", url, e)),
+        }
+    }
+}
+
+fn check_config(config: &Config, report: &mut ConfigValidation) {
+    let upload = &config.upload;
+    if upload.url.is_empty() && upload.backends.is_empty() {
+        report.warning("upload.url", "No upload server configured; uploads are disabled");
+    }
+    if !upload.url.is_empty() {
+        report.check_url("upload.url", &upload.url);
+        if upload.token.is_empty() && upload.headers.is_empty() {
+            report.error("upload.token", "upload.url is set but neither upload.token nor upload.headers is");
+        }
+    }
+    for (i, backend) in upload.backends.iter().enumerate() {
+        let field = format!("upload.backends[{}]", i);
+        if backend.name.is_empty() || backend.url.is_empty() || backend.token.is_empty() {
+            report.error(&field, "Backend requires name, url and token");
+        }
+        if !backend.url.is_empty() {
+            report.check_url(&format!("{}.url", field), &backend.url);
+        }
+        if upload.backends[..i].iter().any(|b| b.name == backend.name) {
+            report.error(&field, format!("Duplicate upload backend name '{}'", backend.name));
+        }
+    }
+    if !upload.default_backend.is_empty() && !upload.backends.iter().any(|b| b.name == upload.default_backend) {
+        report.error("upload.default_backend", format!("'{}' is not a configured backend", upload.default_backend));
+    }
+    if upload.quality == 0 || upload.quality > 100 {
+        report.warning("upload.quality", "Quality should be 1-100; it will be clamped");
+    }
+    for (name, value) in &upload.headers {
+        if let Err(e) = super::upload::parse_header(name, value) {
+            report.warning(&format!("upload.headers.{}", name), format!("{}; it is skipped", e));
+        }
+    }
+    if upload.field_name.trim().is_empty() {
+        report.error("upload.field_name", "Multipart field name must not be empty");
+    }
+    if let Err(e) = upload.ca_certificate() {
+        report.error("upload.ca_cert_path", e.to_string());
+    }
+    if upload.danger_accept_invalid_certs {
+        report.warning("upload.danger_accept_invalid_certs", "TLS certificate verification is disabled for uploads");
+    }
+
+    if let Err(e) = super::shortcuts::parse_shortcut(&config.shortcuts.upload) {
+        report.error("shortcuts.upload", format!("{}; the default {} is used instead", e, super::shortcuts::DEFAULT_UPLOAD_SHORTCUT));
+    }
+    if let Err(e) = super::shortcuts::parse_shortcut(&config.shortcuts.toggle_window) {
+        report.error("shortcuts.toggle_window", format!("{}; the default {} is used instead", e, super::shortcuts::DEFAULT_TOGGLE_WINDOW_SHORTCUT));
+    }
+    if let Err(e) = super::shortcuts::parse_shortcut(&config.shortcuts.capture) {
+        report.error("shortcuts.capture", format!("{}; the default {} is used instead", e, super::shortcuts::DEFAULT_CAPTURE_SHORTCUT));
+    }
+    if config.shortcuts.toggle_window.eq_ignore_ascii_case(&config.shortcuts.upload) {
+        report.error("shortcuts.toggle_window", "Same as shortcuts.upload");
+    }
+    if config.shortcuts.capture.eq_ignore_ascii_case(&config.shortcuts.upload) {
+        report.error("shortcuts.capture", "Same as shortcuts.upload");
+    } else if config.shortcuts.capture.eq_ignore_ascii_case(&config.shortcuts.toggle_window) {
+        report.error("shortcuts.capture", "Same as shortcuts.toggle_window");
+    }
+
+    if config.mijia.api_base.is_empty() != config.mijia.api_key.is_empty() {
+        report.error("mijia", "api_base and api_key must be set together");
+    }
+    if !config.mijia.api_base.is_empty() {
+        report.check_url("mijia.api_base", &config.mijia.api_base);
+    }
+    if config.mijia.poll_playback && config.mijia.default_did.is_empty() {
+        report.error("mijia.poll_playback", "Needs default_did to know which device to poll");
+    }
+
+    if !config.general.log_level.is_empty() && config.general.log_level.parse::<log::LevelFilter>().is_err() {
+        report.warning("general.log_level", format!("Unknown level '{}'; info is used", config.general.log_level));
+    }
+    if let Err(e) = config.general.proxy() {
+        report.error("general.proxy", e.to_string());
+    }
+
+    if config.metrics.enabled {
+        match config.metrics.address.parse::<std::net::SocketAddr>() {
+            Ok(addr) if !addr.ip().is_loopback() => {
+                report.warning("metrics.address", "Not a loopback address; metrics are reachable from the network");
+            }
+            Ok(_) => {}
+            Err(e) => report.error("metrics.address", format!("Invalid address '{}': {}", config.metrics.address, e)),
+        }
+    }
+
+    let mount_point = &config.disk.mount_point;
+    if mount_point != "/" && !std::path::Path::new(mount_point).is_dir() {
+        report.warning("disk.mount_point", format!("'{}' is not mounted; / is reported instead", mount_point));
+    }
+
+    let tray = &config.tray;
+    if tray.refresh_interval_ms > 0 && tray.refresh_interval_ms < MIN_REFRESH_INTERVAL_MS {
+        report.warning("tray.refresh_interval_ms", format!("Clamped to the {}ms minimum", MIN_REFRESH_INTERVAL_MS));
+    }
+    if tray.smoothing_alpha <= 0.0 || tray.smoothing_alpha > 1.0 {
+        report.warning("tray.smoothing_alpha", "Must be in (0, 1]; smoothing is disabled");
+    }
+    if tray.network.warn_mbps > tray.network.critical_mbps {
+        report.warning("tray.network", "warn_mbps is above critical_mbps");
+    }
+    if tray.memory.warn_percent > tray.memory.critical_percent {
+        report.warning("tray.memory", "warn_percent is above critical_percent");
+    }
+}
+
+/// Check config.toml for mistakes without applying it
+#[tauri::command]
+pub fn validate_config() -> ConfigValidation {
+    let config_path = get_config_path();
+    let mut report = ConfigValidation {
+        path: config_path.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+
+    if !config_path.exists() {
+        report.warning("", "Config file not found; defaults are used");
+        return report;
+    }
+
+    let contents = match fs::read_to_string(&config_path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            report.error("", format!("Failed to read config file: {}", e));
+            return report;
+        }
+    };
+
+    match toml::from_str::<Config>(&contents) {
+        Ok(config) => check_config(&config, &mut report),
+        Err(e) => report.error("", format!("Failed to parse config: {}", e)),
+    }
+
+    report
+}
+
+/// Apply `update` to the config on disk and save it. Unlike `load_config`, a
+/// config.toml that fails to parse is an error, so it never gets overwritten
+/// with defaults.
+pub fn update_config(update: impl FnOnce(&mut Config)) -> Result<String, PulseError> {
+    let config_path = get_config_path();
+    let mut config = if config_path.exists() {
+        let contents = fs::read_to_string(&config_path)
+            .map_err(|e| PulseError::Io(format!("Failed to read config file: {}", e)))?;
+        toml::from_str(&contents)
+            .map_err(|e| PulseError::Parse(format!("Failed to parse config: {}", e)))?
+    } else {
+        Config::default()
+    };
+    update(&mut config);
+    save_config(config)
+}
+
+/// Validate and persist config, writing a temp file then renaming it over
+/// config.toml so a crash mid-write can't corrupt it. Returns the path written.
+#[tauri::command]
+pub fn save_config(config: Config) -> Result<String, PulseError> {
+    let mut report = ConfigValidation::default();
+    check_config(&config, &mut report);
+    if let Some(issue) = report.errors.first() {
+        return Err(PulseError::Invalid(format!("{}: {}", issue.field, issue.message)));
+    }
+
+    let config_path = get_config_path();
+    if let Some(dir) = config_path.parent() {
+        fs::create_dir_all(dir).map_err(|e| PulseError::Io(format!("Failed to create config directory: {}", e)))?;
+    }
+
+    let contents = toml::to_string_pretty(&config).map_err(|e| PulseError::Encode(format!("Failed to serialize config: {}", e)))?;
+
+    let tmp_path = config_path.with_extension("toml.tmp");
+    fs::write(&tmp_path, contents).map_err(|e| PulseError::Io(format!("Failed to write config: {}", e)))?;
+    fs::rename(&tmp_path, &config_path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        PulseError::Io(format!("Failed to replace config file: {}", e))
+    })?;
+
+    log::info!("Config saved to {:?}", config_path);
+    Ok(config_path.to_string_lossy().to_string())
+}
+
 /// Get mijia config for frontend
 #[tauri::command]
 pub fn get_mijia_config() -> MijiaConfig {
@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+use crate::modules::AppState;
+use crate::modules::config::{get_config_dir, load_config};
+use crate::modules::utils::LockExt;
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+const PERSIST_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Network usage accumulated for a single local day
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct DailyDataUsage {
+    pub date: String,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    #[serde(default)]
+    pub cap_notified: bool,
+    #[serde(skip)]
+    last_saved: Option<Instant>,
+}
+
+#[derive(Serialize)]
+pub struct DailyDataUsageReport {
+    pub date: String,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub total_bytes: u64,
+    pub cap_gb: Option<f64>,
+    pub over_cap: bool,
+}
+
+impl DailyDataUsage {
+    fn today() -> Self {
+        Self {
+            date: today(),
+            ..Default::default()
+        }
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.bytes_sent + self.bytes_received
+    }
+
+    /// Reset the counters when the local date has changed since they were stored
+    fn roll_over(&mut self) {
+        let today = today();
+        if self.date != today {
+            log::info!("New day {}, resetting data usage (was {})", today, self.date);
+            *self = Self::today();
+        }
+    }
+}
+
+fn today() -> String {
+    chrono::Local::now().date_naive().to_string()
+}
+
+fn over_cap(total_bytes: u64, cap_gb: Option<f64>) -> bool {
+    cap_gb.is_some_and(|cap| cap > 0.0 && total_bytes as f64 >= cap * BYTES_PER_GB)
+}
+
+/// Data usage file: stored next to config.toml
+fn get_data_usage_path() -> PathBuf {
+    get_config_dir().join("data_usage.json")
+}
+
+/// Load today's usage, starting fresh if the stored date is not today
+pub fn load_data_usage() -> DailyDataUsage {
+    let path = get_data_usage_path();
+    let mut usage = fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<DailyDataUsage>(&contents).ok())
+        .unwrap_or_else(DailyDataUsage::today);
+    usage.roll_over();
+    usage
+}
+
+fn save_data_usage(usage: &DailyDataUsage) {
+    let path = get_data_usage_path();
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    match serde_json::to_string(usage) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                log::error!("Failed to save data usage: {}", e);
+            }
+        }
+        Err(e) => log::error!("Failed to encode data usage: {}", e),
+    }
+}
+
+/// Add a network sample to today's usage, notifying once when the daily cap is
+/// crossed. Returns whether today's usage is over the cap.
+pub fn record_data_usage(app: &AppHandle, sent: u64, received: u64, cap_gb: Option<f64>) -> bool {
+    let state = app.state::<AppState>();
+    let mut usage = state.data_usage.lock_or_recover();
+
+    usage.roll_over();
+    usage.bytes_sent += sent;
+    usage.bytes_received += received;
+
+    let is_over_cap = over_cap(usage.total_bytes(), cap_gb);
+    if !usage.cap_notified && is_over_cap {
+        usage.cap_notified = true;
+        let cap = cap_gb.unwrap_or_default();
+        log::warn!("Daily data cap of {} GB reached", cap);
+
+        let _ = app
+            .notification()
+            .builder()
+            .title("Pulse")
+            .body(format!("Daily data cap of {} GB reached", cap))
+            .show();
+        let _ = app.emit("data-cap-exceeded", cap);
+
+        save_data_usage(&usage);
+        usage.last_saved = Some(Instant::now());
+    } else if usage.last_saved.map_or(true, |t| t.elapsed() >= PERSIST_INTERVAL) {
+        save_data_usage(&usage);
+        usage.last_saved = Some(Instant::now());
+    }
+    is_over_cap
+}
+
+/// Get today's network usage and the configured daily cap
+#[tauri::command]
+pub fn get_daily_data_usage(state: State<AppState>) -> DailyDataUsageReport {
+    let cap_gb = load_config().general.daily_data_cap_gb;
+    let mut usage = state.data_usage.lock_or_recover();
+    usage.roll_over();
+
+    DailyDataUsageReport {
+        date: usage.date.clone(),
+        bytes_sent: usage.bytes_sent,
+        bytes_received: usage.bytes_received,
+        total_bytes: usage.total_bytes(),
+        cap_gb,
+        over_cap: over_cap(usage.total_bytes(), cap_gb),
+    }
+}
@@ -0,0 +1,34 @@
+use std::process::Command;
+use tauri::AppHandle;
+use crate::modules::Diagnostics;
+use crate::modules::config::{get_config_path, load_config, validate_config};
+use crate::modules::logging::{error_count, get_log_path};
+
+/// macOS product version from `sw_vers`, e.g. "14.5"
+fn os_version() -> Option<String> {
+    let output = Command::new("sw_vers").arg("-productVersion").output().ok()?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !version.is_empty()).then_some(version)
+}
+
+/// Version and configuration summary to paste into an issue
+#[tauri::command]
+pub fn get_diagnostics(app: AppHandle) -> Diagnostics {
+    let config_path = get_config_path();
+    let config = load_config();
+    let upload_configured = config.upload.resolve_backend(None).is_ok();
+
+    Diagnostics {
+        app_version: app.package_info().version.to_string(),
+        os_version: os_version(),
+        config_path: config_path.to_string_lossy().to_string(),
+        config_exists: config_path.exists(),
+        config_errors: validate_config().errors.len(),
+        upload_configured,
+        upload_backends: config.upload.backends.len(),
+        mijia_configured: !config.mijia.api_base.is_empty() && !config.mijia.api_key.is_empty(),
+        proxy_configured: !config.general.proxy.trim().is_empty(),
+        log_path: get_log_path(),
+        error_count: error_count(),
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+/// Structured command error. Serialized as `{ kind, message, status?, raw_response? }`
+/// so the frontend can branch on `kind` and still show `message`.
+#[derive(Debug, Clone)]
+pub enum PulseError {
+    /// Missing or invalid configuration
+    Config(String),
+    /// Connection, timeout, or client setup failure
+    Network(String),
+    /// Clipboard access or content failure
+    Clipboard(String),
+    /// Image/data encoding or decoding failure
+    Encode(String),
+    /// Filesystem read/write failure
+    Io(String),
+    /// Unexpected response body from a server
+    Parse(String),
+    /// Success status but a body that can't be used, e.g. missing the URL field
+    BadResponse { message: String, raw_response: String },
+    /// Invalid argument passed to a command
+    Invalid(String),
+    /// Data that isn't a PNG/JPEG/GIF/WebP image
+    UnsupportedImage(String),
+    /// Non-success HTTP status from a server, with the (truncated) body when kept
+    Api { status: u16, message: String, raw_response: Option<String> },
+    /// Operation needs privileges the app doesn't have (e.g. root)
+    Permission(String),
+    /// Stopped by the user, e.g. via cancel_upload
+    Cancelled,
+}
+
+impl PulseError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PulseError::Config(_) => "config",
+            PulseError::Network(_) => "network",
+            PulseError::Clipboard(_) => "clipboard",
+            PulseError::Encode(_) => "encode",
+            PulseError::Io(_) => "io",
+            PulseError::Parse(_) | PulseError::BadResponse { .. } => "parse",
+            PulseError::Invalid(_) => "invalid",
+            PulseError::UnsupportedImage(_) => "unsupported_image",
+            PulseError::Api { .. } => "api",
+            PulseError::Permission(_) => "permission",
+            PulseError::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            PulseError::Config(message)
+            | PulseError::Network(message)
+            | PulseError::Clipboard(message)
+            | PulseError::Encode(message)
+            | PulseError::Io(message)
+            | PulseError::Parse(message)
+            | PulseError::Invalid(message)
+            | PulseError::UnsupportedImage(message)
+            | PulseError::Permission(message)
+            | PulseError::BadResponse { message, .. }
+            | PulseError::Api { message, .. } => message,
+            PulseError::Cancelled => "Cancelled",
+        }
+    }
+
+    /// Server response body behind the error, for debugging
+    pub fn raw_response(&self) -> Option<&str> {
+        match self {
+            PulseError::BadResponse { raw_response, .. } => Some(raw_response),
+            PulseError::Api { raw_response, .. } => raw_response.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PulseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for PulseError {}
+
+impl Serialize for PulseError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let status = match self {
+            PulseError::Api { status, .. } => Some(*status),
+            _ => None,
+        };
+        let raw_response = self.raw_response();
+        let len = 2 + status.is_some() as usize + raw_response.is_some() as usize;
+        let mut state = serializer.serialize_struct("PulseError", len)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", self.message())?;
+        if let Some(status) = status {
+            state.serialize_field("status", &status)?;
+        }
+        if let Some(raw_response) = raw_response {
+            state.serialize_field("raw_response", raw_response)?;
+        }
+        state.end()
+    }
+}
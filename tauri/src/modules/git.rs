@@ -1,7 +1,11 @@
-use std::process::Command;
-use std::path::PathBuf;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
 use serde::Serialize;
 use tauri::command;
+use crate::modules::utils::expand_home;
 
 #[derive(Serialize, Debug)]
 pub struct GitBranch {
@@ -15,19 +19,131 @@ pub struct GitState {
     repo_path: String,
 }
 
+#[derive(Serialize, Debug, Default)]
+pub struct GitStatus {
+    branch: Option<String>,
+    staged: Vec<String>,
+    modified: Vec<String>,
+    untracked: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct GitCommit {
+    hash: String,
+    author: String,
+    /// Author date, ISO 8601
+    date: String,
+    subject: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct GitDiff {
+    diff: String,
+    /// The diff was cut at MAX_DIFF_BYTES
+    truncated: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct GitStash {
+    /// N in `stash@{N}`, 0 is the most recent
+    index: usize,
+    message: String,
+}
+
 fn get_claude_path() -> PathBuf {
     let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
     PathBuf::from(home).join(".claude")
 }
 
-#[command]
-pub fn get_git_branches() -> Result<GitState, String> {
-    let repo_path = get_claude_path();
+/// Resolve the repo: explicit override, then `[git] repo_path`, then ~/.claude
+fn resolve_repo_path(path: Option<String>) -> Result<PathBuf, String> {
+    let configured = super::config::load_config().git.repo_path;
+    let repo_path = match path.filter(|p| !p.is_empty()) {
+        Some(p) => expand_home(&p),
+        None if !configured.is_empty() => expand_home(&configured),
+        None => get_claude_path(),
+    };
 
-    // Check if directory exists
     if !repo_path.exists() {
         return Err(format!("Repository path does not exist: {:?}", repo_path));
     }
+    if !repo_path.join(".git").exists() {
+        return Err(format!("Not a git repository (no .git found): {:?}", repo_path));
+    }
+
+    Ok(repo_path)
+}
+
+const GIT_NETWORK_TIMEOUT: Duration = Duration::from_secs(60);
+/// Local commands that can still be slow on a big repo, e.g. diff
+const GIT_LOCAL_TIMEOUT: Duration = Duration::from_secs(15);
+
+struct GitOutput {
+    success: bool,
+    stdout: String,
+    stderr: String,
+}
+
+impl GitOutput {
+    fn combined(&self) -> String {
+        format!("{}{}", self.stdout, self.stderr).trim().to_string()
+    }
+}
+
+/// Run git in `repo_path`, killing it if it runs longer than `timeout`
+fn run_git_with_timeout(repo_path: &Path, args: &[&str], timeout: Duration) -> Result<GitOutput, String> {
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(repo_path)
+        // Never block on an interactive credential prompt
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+
+    // Drain pipes on separate threads so a chatty command can't fill the buffer and stall
+    let mut stdout_pipe = child.stdout.take();
+    let mut stderr_pipe = child.stderr.take();
+    let stdout_reader = thread::spawn(move || {
+        let mut buf = String::new();
+        if let Some(pipe) = stdout_pipe.as_mut() {
+            let _ = pipe.read_to_string(&mut buf);
+        }
+        buf
+    });
+    let stderr_reader = thread::spawn(move || {
+        let mut buf = String::new();
+        if let Some(pipe) = stderr_pipe.as_mut() {
+            let _ = pipe.read_to_string(&mut buf);
+        }
+        buf
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("git {} timed out after {}s", args.join(" "), timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(100)),
+            Err(e) => return Err(format!("Failed to wait for git command: {}", e)),
+        }
+    };
+
+    Ok(GitOutput {
+        success: status.success(),
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+#[command]
+pub fn get_git_branches(path: Option<String>) -> Result<GitState, String> {
+    let repo_path = resolve_repo_path(path)?;
 
     let output = Command::new("git")
         .arg("branch")
@@ -48,10 +164,9 @@ pub fn get_git_branches() -> Result<GitState, String> {
             continue;
         }
 
-        let (name, current) = if trimmed.starts_with('*') {
-            (trimmed[1..].trim().to_string(), true)
-        } else {
-            (trimmed.to_string(), false)
+        let (name, current) = match trimmed.strip_prefix('*') {
+            Some(rest) => (rest.trim().to_string(), true),
+            None => (trimmed.to_string(), false),
         };
 
         branches.push(GitBranch { name, current });
@@ -63,19 +178,81 @@ pub fn get_git_branches() -> Result<GitState, String> {
     })
 }
 
+/// Levenshtein distance over chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        prev = current;
+    }
+    prev[b.len()]
+}
+
+/// Closest branch name to `name`, if it's near enough to be a likely typo
+fn suggest_branch<'a>(name: &str, branches: &'a [String]) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(2);
+    branches
+        .iter()
+        .map(|branch| (edit_distance(name, branch), branch.as_str()))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+/// Remote-tracking branches, both as `origin/feature` and as the bare `feature`
+/// that `git checkout` turns into a new tracking branch
+fn remote_branch_names(repo_path: &Path) -> Vec<String> {
+    let output = match Command::new("git")
+        .args(["for-each-ref", "--format=%(refname:lstrip=2)", "refs/remotes"])
+        .current_dir(repo_path)
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+
+    let mut names = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((_, short)) = line.trim().split_once('/') else {
+            continue;
+        };
+        if short == "HEAD" {
+            continue;
+        }
+        names.push(line.trim().to_string());
+        names.push(short.to_string());
+    }
+    names
+}
+
 #[command]
-pub fn switch_git_branch(branch: String) -> Result<String, String> {
-    let repo_path = get_claude_path();
+pub fn switch_git_branch(branch: String, path: Option<String>) -> Result<String, String> {
+    let repo_path = resolve_repo_path(path.clone())?;
+
+    // Check before discarding anything below, so a stale selection costs nothing
+    let mut branches: Vec<String> = get_git_branches(path)?.branches.into_iter().map(|b| b.name).collect();
+    branches.extend(remote_branch_names(&repo_path));
+    if !branches.contains(&branch) {
+        return Err(match suggest_branch(&branch, &branches) {
+            Some(suggestion) => format!("No such branch '{}'; did you mean '{}'?", branch, suggestion),
+            None => format!("No such branch '{}'", branch),
+        });
+    }
 
     // 先丢弃未提交的更改
     let _ = Command::new("git")
-        .args(&["checkout", "--", "."])
+        .args(["checkout", "--", "."])
         .current_dir(&repo_path)
         .output();
 
     // 清除未跟踪的文件
     let _ = Command::new("git")
-        .args(&["clean", "-fd"])
+        .args(["clean", "-fd"])
         .current_dir(&repo_path)
         .output();
 
@@ -93,3 +270,300 @@ pub fn switch_git_branch(branch: String) -> Result<String, String> {
 
     Ok(format!("Switched to branch {}", branch))
 }
+
+/// Parse the `## branch...upstream` header of `git status --porcelain -b`
+fn parse_branch_header(header: &str) -> Option<String> {
+    let branch = header
+        .trim_start_matches("No commits yet on ")
+        .trim_start_matches("Initial commit on ")
+        .split("...")
+        .next()?
+        .trim();
+
+    if branch.is_empty() || branch.starts_with("HEAD (no branch)") {
+        None
+    } else {
+        Some(branch.to_string())
+    }
+}
+
+#[command]
+pub fn get_git_status(path: Option<String>) -> Result<GitStatus, String> {
+    let repo_path = resolve_repo_path(path)?;
+
+    let output = Command::new("git")
+        .args(["status", "--porcelain", "-b"])
+        .current_dir(&repo_path)
+        .output()
+        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("Git command failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut status = GitStatus::default();
+
+    for line in stdout.lines() {
+        if let Some(header) = line.strip_prefix("## ") {
+            status.branch = parse_branch_header(header);
+            continue;
+        }
+        if line.len() < 4 {
+            continue;
+        }
+
+        // "XY path", or "XY old -> new" for renames
+        let (index, worktree) = (line.as_bytes()[0], line.as_bytes()[1]);
+        let path = line[3..].rsplit(" -> ").next().unwrap_or(&line[3..]).to_string();
+
+        if index == b'?' && worktree == b'?' {
+            status.untracked.push(path);
+            continue;
+        }
+        if index != b' ' {
+            status.staged.push(path.clone());
+        }
+        if worktree != b' ' {
+            status.modified.push(path);
+        }
+    }
+
+    Ok(status)
+}
+
+/// Field and record separators for `git log`; subjects can contain anything printable
+const LOG_FIELD_SEP: char = '\x1f';
+const LOG_RECORD_SEP: char = '\x1e';
+
+/// The most recent `limit` commits on the current branch, newest first
+#[command]
+pub fn get_git_log(limit: usize, path: Option<String>) -> Result<Vec<GitCommit>, String> {
+    let repo_path = resolve_repo_path(path)?;
+
+    // git log exits nonzero on a repo without commits; that's just an empty log
+    let head = Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+        .current_dir(&repo_path)
+        .output()
+        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+    if !head.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let output = Command::new("git")
+        .arg("log")
+        .arg(format!("--max-count={}", limit))
+        .arg("--pretty=format:%H%x1f%an%x1f%aI%x1f%s%x1e")
+        .current_dir(&repo_path)
+        .output()
+        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("Git command failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let commits = stdout
+        .split(LOG_RECORD_SEP)
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').splitn(4, LOG_FIELD_SEP);
+            Some(GitCommit {
+                hash: fields.next().filter(|hash| !hash.is_empty())?.to_string(),
+                author: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                subject: fields.next()?.to_string(),
+            })
+        })
+        .collect();
+
+    Ok(commits)
+}
+
+/// Diffs beyond this are cut so the webview doesn't choke rendering them
+const MAX_DIFF_BYTES: usize = 512 * 1024;
+
+/// Unified diff of the working tree against the index, or of the index
+/// against HEAD when `staged`
+#[command]
+pub fn get_git_diff(staged: bool, path: Option<String>) -> Result<GitDiff, String> {
+    let repo_path = resolve_repo_path(path)?;
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+    if staged {
+        args.push("--cached");
+    }
+    let output = run_git_with_timeout(&repo_path, &args, GIT_LOCAL_TIMEOUT)?;
+
+    if !output.success {
+        return Err(format!("Git diff failed: {}", output.combined()));
+    }
+
+    let mut diff = output.stdout;
+    let truncated = diff.len() > MAX_DIFF_BYTES;
+    if truncated {
+        let mut end = MAX_DIFF_BYTES;
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        diff.truncate(end);
+    }
+
+    Ok(GitDiff { diff, truncated })
+}
+
+#[command(async)]
+pub fn git_pull(path: Option<String>) -> Result<String, String> {
+    let repo_path = resolve_repo_path(path)?;
+    let output = run_git_with_timeout(&repo_path, &["pull"], GIT_NETWORK_TIMEOUT)?;
+
+    if !output.success {
+        return Err(format!("Git pull failed: {}", output.combined()));
+    }
+
+    Ok(output.combined())
+}
+
+#[command(async)]
+pub fn git_push(path: Option<String>) -> Result<String, String> {
+    let repo_path = resolve_repo_path(path)?;
+    let output = run_git_with_timeout(&repo_path, &["push"], GIT_NETWORK_TIMEOUT)?;
+
+    if !output.success {
+        if output.stderr.contains("has no upstream branch") {
+            // git suggests the exact command, e.g. "git push --set-upstream origin main"
+            let suggestion = output
+                .stderr
+                .lines()
+                .map(|line| line.trim())
+                .find(|line| line.starts_with("git push --set-upstream"))
+                .unwrap_or("git push --set-upstream origin <branch>");
+            return Err(format!("The current branch has no upstream branch. Set one with: {}", suggestion));
+        }
+        return Err(format!("Git push failed: {}", output.combined()));
+    }
+
+    Ok(output.combined())
+}
+
+/// Stash all changes, untracked files included, so a branch switch can't discard them
+#[command]
+pub fn git_stash_save(message: Option<String>, path: Option<String>) -> Result<String, String> {
+    let repo_path = resolve_repo_path(path)?;
+    let mut args = vec!["stash", "push", "--include-untracked"];
+    if let Some(message) = message.as_deref().filter(|m| !m.trim().is_empty()) {
+        args.extend(["-m", message]);
+    }
+    let output = run_git_with_timeout(&repo_path, &args, GIT_LOCAL_TIMEOUT)?;
+
+    if !output.success {
+        return Err(format!("Git stash failed: {}", output.combined()));
+    }
+
+    Ok(output.combined())
+}
+
+#[command]
+pub fn git_stash_list(path: Option<String>) -> Result<Vec<GitStash>, String> {
+    let repo_path = resolve_repo_path(path)?;
+    let output = run_git_with_timeout(&repo_path, &["stash", "list", "--format=%gd%x1f%gs"], GIT_LOCAL_TIMEOUT)?;
+
+    if !output.success {
+        return Err(format!("Git stash list failed: {}", output.combined()));
+    }
+
+    let stashes = output
+        .stdout
+        .lines()
+        .filter_map(|line| {
+            let (name, message) = line.split_once(LOG_FIELD_SEP)?;
+            let index = name.strip_prefix("stash@{")?.strip_suffix('}')?.parse().ok()?;
+            Some(GitStash { index, message: message.to_string() })
+        })
+        .collect();
+
+    Ok(stashes)
+}
+
+/// Apply and drop the most recent stash
+#[command]
+pub fn git_stash_pop(path: Option<String>) -> Result<String, String> {
+    if git_stash_list(path.clone())?.is_empty() {
+        return Err("No stashes to pop".to_string());
+    }
+
+    let repo_path = resolve_repo_path(path)?;
+    let output = run_git_with_timeout(&repo_path, &["stash", "pop"], GIT_LOCAL_TIMEOUT)?;
+
+    if !output.success {
+        return Err(format!("Git stash pop failed: {}", output.combined()));
+    }
+
+    Ok(output.combined())
+}
+
+/// Check a branch name against git's ref naming rules (see `git check-ref-format`)
+fn validate_branch_name(name: &str) -> Result<(), String> {
+    let invalid = |reason: &str| Err(format!("Invalid branch name '{}': {}", name, reason));
+
+    if name.is_empty() {
+        return invalid("name is empty");
+    }
+    if name.starts_with('-') {
+        return invalid("cannot start with '-'");
+    }
+    if name.contains("..") {
+        return invalid("cannot contain '..'");
+    }
+    if name.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return invalid("cannot contain spaces or control characters");
+    }
+    if let Some(c) = name.chars().find(|&c| matches!(c, '~' | '^' | ':' | '?' | '*' | '[' | '\\')) {
+        return invalid(&format!("cannot contain '{}'", c));
+    }
+    if name.contains("@{") || name == "@" {
+        return invalid("cannot contain '@{' or be '@'");
+    }
+    if name.starts_with('/') || name.ends_with('/') || name.contains("//") {
+        return invalid("cannot start or end with '/' or contain '//'");
+    }
+    if name.ends_with('.') || name.ends_with(".lock") {
+        return invalid("cannot end with '.' or '.lock'");
+    }
+    if name.split('/').any(|component| component.starts_with('.')) {
+        return invalid("path components cannot start with '.'");
+    }
+
+    Ok(())
+}
+
+#[command]
+pub fn create_git_branch(name: String, checkout: bool, path: Option<String>) -> Result<GitState, String> {
+    validate_branch_name(&name)?;
+    let repo_path = resolve_repo_path(path.clone())?;
+
+    let output = Command::new("git")
+        .arg("branch")
+        .arg(&name)
+        .current_dir(&repo_path)
+        .output()
+        .map_err(|e| format!("Failed to execute git command: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("Failed to create branch: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    if checkout {
+        let output = Command::new("git")
+            .arg("checkout")
+            .arg(&name)
+            .current_dir(&repo_path)
+            .output()
+            .map_err(|e| format!("Failed to execute git command: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!("Failed to switch branch: {}", String::from_utf8_lossy(&output.stderr)));
+        }
+    }
+
+    get_git_branches(path)
+}
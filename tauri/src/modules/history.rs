@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use crate::modules::UploadResult;
+use crate::modules::tray::update_copy_url_item;
+use crate::modules::config::{get_config_dir, load_config};
+
+/// Guards read-modify-write of history.json across the shortcut thread and commands
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub url: String,
+    pub filename: Option<String>,
+    pub size: Option<String>,
+    /// Unix timestamp in seconds
+    pub timestamp: i64,
+    /// SHA-256 of the uploaded bytes (hex)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// History file: ~/.config/pulse/history.json
+fn get_history_path() -> PathBuf {
+    get_config_dir().join("history.json")
+}
+
+fn read_history() -> Vec<HistoryEntry> {
+    let path = get_history_path();
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            log::error!("Failed to parse upload history: {}", e);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn write_history(entries: &[HistoryEntry]) -> Result<(), String> {
+    let path = get_history_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create history directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(entries).map_err(|e| format!("Failed to encode history: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write history: {}", e))
+}
+
+/// Append a successful upload, evicting the oldest entries beyond `upload.history_limit`
+pub fn record_upload(result: &UploadResult) {
+    let Some(url) = result.url.clone() else {
+        return;
+    };
+    if result.duplicate {
+        return;
+    }
+    let limit = load_config().upload.history_limit;
+
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let mut entries = read_history();
+    entries.push(HistoryEntry {
+        url,
+        filename: result.filename.clone(),
+        size: result.size.clone(),
+        timestamp: chrono::Utc::now().timestamp(),
+        sha256: result.sha256.clone(),
+    });
+    if entries.len() > limit {
+        let excess = entries.len() - limit;
+        entries.drain(..excess);
+    }
+
+    if let Err(e) = write_history(&entries) {
+        log::error!("{}", e);
+    }
+}
+
+/// URL of the most recent upload, if any
+pub fn last_upload_url() -> Option<String> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    read_history().pop().map(|entry| entry.url)
+}
+
+/// Most recent upload of content with this SHA-256
+pub fn find_upload_by_hash(sha256: &str) -> Option<HistoryEntry> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    read_history().into_iter().rev().find(|entry| entry.sha256.as_deref() == Some(sha256))
+}
+
+/// Get upload history, oldest first
+#[tauri::command]
+pub fn get_upload_history() -> Vec<HistoryEntry> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    read_history()
+}
+
+/// Remove all upload history entries
+#[tauri::command]
+pub fn clear_upload_history(app: AppHandle) -> Result<(), String> {
+    let result = {
+        let _guard = HISTORY_LOCK.lock().unwrap();
+        write_history(&[])
+    };
+    update_copy_url_item(&app);
+    result
+}
@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+use crate::modules::config::{get_config_dir, GeneralConfig};
+use crate::modules::error::PulseError;
+
+/// Start a new file once the current one reaches this size
+const MAX_LOG_FILE_BYTES: u128 = 5 * 1024 * 1024;
+
+/// Error records logged since launch, reported by get_diagnostics
+static ERROR_COUNT: AtomicU64 = AtomicU64::new(0);
+
+pub fn error_count() -> u64 {
+    ERROR_COUNT.load(Ordering::Relaxed)
+}
+
+/// Log folder next to config.toml, e.g. ~/.config/pulse/logs
+pub fn log_dir() -> PathBuf {
+    get_config_dir().join("logs")
+}
+
+/// Logger writing to stdout and a per-day file (pulse-YYYY-MM-DD.log) in `log_dir`
+pub fn build_logger<R: tauri::Runtime>(general: &GeneralConfig) -> tauri::plugin::TauriPlugin<R> {
+    let file_name = format!("pulse-{}", chrono::Local::now().format("%Y-%m-%d"));
+    tauri_plugin_log::Builder::new()
+        .clear_targets()
+        .target(Target::new(TargetKind::Stdout))
+        .target(Target::new(TargetKind::Folder { path: log_dir(), file_name: Some(file_name) }))
+        .level(general.log_level())
+        .format(|out, message, record| {
+            if record.level() == log::Level::Error {
+                ERROR_COUNT.fetch_add(1, Ordering::Relaxed);
+            }
+            out.finish(format_args!(
+                "[{}][{}][{}] {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                record.level(),
+                record.target(),
+                message
+            ))
+        })
+        .max_file_size(MAX_LOG_FILE_BYTES)
+        .rotation_strategy(RotationStrategy::KeepAll)
+        .build()
+}
+
+/// Folder containing the log files, for attaching to bug reports
+#[tauri::command]
+pub fn get_log_path() -> String {
+    log_dir().to_string_lossy().to_string()
+}
+
+/// Open the log folder in Finder
+pub fn reveal_logs() -> Result<(), PulseError> {
+    let dir = log_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| PulseError::Io(format!("Failed to create log folder: {}", e)))?;
+    Command::new("open")
+        .arg(&dir)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| PulseError::Io(format!("Failed to open log folder: {}", e)))
+}
@@ -0,0 +1,82 @@
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use crate::modules::{AppState, SystemStats};
+use crate::modules::config::MetricsConfig;
+use crate::modules::utils::LockExt;
+
+/// Serve the tray loop's latest sample at http://<address>/metrics in the
+/// Prometheus text format, on its own thread
+pub fn start_metrics_server(app: AppHandle, config: &MetricsConfig) {
+    if !config.enabled {
+        return;
+    }
+    let listener = match TcpListener::bind(&config.address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Failed to start metrics endpoint on {}: {}", config.address, e);
+            return;
+        }
+    };
+    log::info!("Serving metrics on http://{}/metrics", config.address);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = handle_connection(&app, stream) {
+                        log::warn!("Metrics request failed: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("Metrics connection failed: {}", e),
+            }
+        }
+    });
+}
+
+fn handle_connection(app: &AppHandle, mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    // "GET /metrics HTTP/1.1"; headers and body are ignored
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+
+    let (status, body) = if method == "GET" && (path == "/metrics" || path.starts_with("/metrics?")) {
+        let stats = app.state::<AppState>().latest_stats.lock_or_recover().clone();
+        ("200 OK", stats.map(|stats| render_metrics(&stats)).unwrap_or_default())
+    } else {
+        ("404 Not Found", "Not found\n".to_string())
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn render_metrics(stats: &SystemStats) -> String {
+    let mut out = String::new();
+    gauge(&mut out, "pulse_cpu_usage_percent", "Global CPU usage in percent.", stats.cpu_usage);
+    if let Some(gpu) = stats.gpu_usage {
+        gauge(&mut out, "pulse_gpu_usage_percent", "GPU active residency in percent.", gpu);
+    }
+    gauge(&mut out, "pulse_memory_used_bytes", "Used memory in bytes.", stats.memory_used);
+    gauge(&mut out, "pulse_memory_total_bytes", "Total memory in bytes.", stats.memory_total);
+    gauge(&mut out, "pulse_disk_usage_percent", "Root volume usage in percent.", stats.disk_usage_percent);
+    gauge(&mut out, "pulse_network_transmit_bytes_per_second", "Upload speed in bytes per second.", stats.network_speed_up);
+    gauge(&mut out, "pulse_network_receive_bytes_per_second", "Download speed in bytes per second.", stats.network_speed_down);
+    out
+}
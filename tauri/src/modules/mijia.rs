@@ -1,7 +1,11 @@
-use std::time::Duration;
-use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use std::sync::RwLock;
 use serde::{Deserialize, Serialize};
 use serde_json;
+use tauri::{AppHandle, Emitter};
+use crate::modules::error::PulseError;
+use crate::modules::config::{Config, MijiaConfig};
+use crate::modules::utils::backoff_delay;
 
 #[derive(Debug, Serialize)]
 pub struct MijiaActionRequest {
@@ -17,43 +21,157 @@ pub struct MijiaActionResponse {
     pub name: String,
 }
 
+/// One entry of the gateway's device list
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MijiaDevice {
+    pub did: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub model: String,
+    #[serde(default, alias = "isOnline")]
+    pub online: bool,
+}
+
 #[derive(Debug, Serialize)]
 pub struct MijiaSetPropRequest<T> {
     pub value: T,
 }
 
-const SPEAKER_DEVICE_ID: &str = "545918099";
-
-fn get_client() -> Result<reqwest::blocking::Client, String> {
-    reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(10))
+fn build_client(config: &Config) -> Result<reqwest::Client, PulseError> {
+    let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(10));
+    if let Some(proxy) = config.general.proxy()? {
+        builder = builder.proxy(proxy);
+    }
+    builder
         .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+        .map_err(|e| PulseError::Network(format!("Failed to create HTTP client: {}", e)))
+}
+
+fn get_client() -> Result<reqwest::Client, PulseError> {
+    if let Some(client) = CACHED_CLIENT.read().unwrap().as_ref() {
+        return Ok(client.clone());
+    }
+
+    let client = build_client(&super::config::load_config())?;
+    *CACHED_CLIENT.write().unwrap() = Some(client.clone());
+    Ok(client)
 }
 
-fn get_config() -> Result<(String, String), String> {
-    static CACHED: OnceLock<Option<(String, String)>> = OnceLock::new();
+static CACHED_CONFIG: RwLock<Option<MijiaConfig>> = RwLock::new(None);
+static CACHED_CLIENT: RwLock<Option<reqwest::Client>> = RwLock::new(None);
 
-    let cached = CACHED.get_or_init(|| {
-        let config = super::config::load_config();
-        if config.mijia.api_base.is_empty() || config.mijia.api_key.is_empty() {
+/// `[mijia]` from the given config, or `None` when api_base/api_key are missing
+fn mijia_section(config: Config) -> Option<MijiaConfig> {
+    if config.mijia.api_base.is_empty() || config.mijia.api_key.is_empty() {
+        None
+    } else {
+        Some(config.mijia)
+    }
+}
+
+fn get_config() -> Result<MijiaConfig, PulseError> {
+    if let Some(config) = CACHED_CONFIG.read().unwrap().as_ref() {
+        return Ok(config.clone());
+    }
+
+    let config = mijia_section(super::config::load_config()).ok_or_else(|| PulseError::Config("Mijia API not configured".to_string()))?;
+    log::info!("Mijia config cached: {}", config.api_base);
+    *CACHED_CONFIG.write().unwrap() = Some(config.clone());
+    Ok(config)
+}
+
+/// Re-read `[mijia]` from config.toml, e.g. after rotating the API key, and
+/// rebuild the HTTP client for the current proxy
+#[tauri::command]
+pub fn reload_mijia_config() -> Result<(), PulseError> {
+    let full_config = super::config::load_config();
+    *CACHED_CLIENT.write().unwrap() = match build_client(&full_config) {
+        Ok(client) => Some(client),
+        Err(e) => {
+            log::warn!("Mijia HTTP client not rebuilt: {}", e);
             None
-        } else {
-            log::info!("Mijia config cached: {}", config.mijia.api_base);
-            Some((config.mijia.api_base, config.mijia.api_key))
         }
-    });
+    };
+
+    let config = mijia_section(full_config);
+    let configured = config.is_some();
+    *CACHED_CONFIG.write().unwrap() = config;
+    *CACHED_DEVICES.write().unwrap() = None;
 
-    cached.as_ref().cloned().ok_or_else(|| "Mijia API not configured".to_string())
+    if configured {
+        log::info!("Mijia config reloaded");
+        Ok(())
+    } else {
+        Err(PulseError::Config("Mijia API not configured".to_string()))
+    }
 }
 
-/// Execute device action
-#[tauri::command]
-pub fn execute_device_action(action: String, params: Option<Vec<String>>) -> Result<MijiaActionResponse, String> {
-    let (api_base, api_key) = get_config()?;
+/// Use the given device id, falling back to `[mijia] default_did`
+fn resolve_did(did: Option<String>, config: &MijiaConfig) -> Result<String, PulseError> {
+    did.filter(|d| !d.is_empty())
+        .or_else(|| Some(config.default_did.clone()).filter(|d| !d.is_empty()))
+        .ok_or_else(|| PulseError::Config("No device id given and no default_did set in [mijia] config".to_string()))
+}
+
+/// Tries per request: the first attempt plus one retry
+const MAX_ATTEMPTS: u32 = 2;
+
+/// Send a request to the gateway at `path` and parse the JSON reply. Timeouts,
+/// connection failures and 5xx are retried after a backoff; 4xx fail at once.
+async fn send_request(config: &MijiaConfig, method: reqwest::Method, path: &str, body: Option<String>) -> Result<serde_json::Value, PulseError> {
     let client = get_client()?;
+    let url = format!("{}{}", config.api_base, path);
+
+    let mut attempt = 0;
+    loop {
+        let mut request = client.request(method.clone(), &url).header("X-API-Key", &config.api_key);
+        if let Some(body) = &body {
+            request = request.header("Content-Type", "application/json").body(body.clone());
+        }
 
-    let url = format!("{}/api/devices/{}/actions/{}", api_base, SPEAKER_DEVICE_ID, action);
+        let err = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                let text = response.text().await.map_err(|e| PulseError::Network(format!("Read response error: {}", e)))?;
+                return serde_json::from_str::<serde_json::Value>(&text).map_err(|e| PulseError::Parse(format!("Parse error: {}", e)));
+            }
+            Ok(response) => {
+                let status = response.status();
+                let err = PulseError::Api {
+                    status: status.as_u16(),
+                    message: format!("API error: {}", status),
+                    raw_response: None,
+                };
+                if !status.is_server_error() {
+                    return Err(err);
+                }
+                err
+            }
+            Err(e) => {
+                let retryable = e.is_timeout() || e.is_connect();
+                let err = PulseError::Network(format!("Request failed: {}", e));
+                if !retryable {
+                    return Err(err);
+                }
+                err
+            }
+        };
+
+        attempt += 1;
+        if attempt >= MAX_ATTEMPTS {
+            return Err(err);
+        }
+        let delay = backoff_delay(attempt - 1);
+        log::warn!("Mijia request {} {} failed, retrying in {:?}: {}", method, path, delay, err);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Execute device action
+#[tauri::command]
+pub async fn execute_device_action(action: String, params: Option<Vec<String>>, did: Option<String>) -> Result<MijiaActionResponse, PulseError> {
+    let config = get_config()?;
+    let did = resolve_did(did, &config)?;
 
     let request_body = if let Some(p) = params {
         MijiaActionRequest {
@@ -63,96 +181,230 @@ pub fn execute_device_action(action: String, params: Option<Vec<String>>) -> Res
         MijiaActionRequest { params: None }
     };
 
-    let body = serde_json::to_string(&request_body).map_err(|e| format!("JSON encode error: {}", e))?;
+    let body = serde_json::to_string(&request_body).map_err(|e| PulseError::Encode(format!("JSON encode error: {}", e)))?;
 
-    let response = client
-        .post(&url)
-        .header("X-API-Key", api_key)
-        .header("Content-Type", "application/json")
-        .body(body)
-        .send()
-        .map_err(|e| format!("Request failed: {}", e))?;
+    let data = send_request(&config, reqwest::Method::POST, &format!("/api/devices/{}/actions/{}", did, action), Some(body)).await?;
+    serde_json::from_value::<MijiaActionResponse>(data).map_err(|e| PulseError::Parse(format!("Parse error: {}", e)))
+}
+
+/// Gateway action names
+const ACTION_PLAY_TEXT: &str = "play-text";
+const ACTION_PLAY: &str = "play";
+const ACTION_PAUSE: &str = "pause";
+const ACTION_NEXT: &str = "next";
+const ACTION_PREV: &str = "previous";
 
-    if !response.status().is_success() {
-        return Err(format!("API error: {}", response.status()));
+/// Make the speaker say `text` (wraps the `play-text` action)
+#[tauri::command]
+pub async fn speak_text(text: String, did: Option<String>) -> Result<MijiaActionResponse, PulseError> {
+    // Collapse newlines/control characters so the speaker reads one clean sentence;
+    // JSON encoding of the params takes care of quoting
+    let text: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if text.is_empty() {
+        return Err(PulseError::Invalid("Text to speak is empty".to_string()));
     }
 
-    let text = response.text().map_err(|e| format!("Read response error: {}", e))?;
-    serde_json::from_str::<MijiaActionResponse>(&text).map_err(|e| format!("Parse error: {}", e))
+    execute_device_action(ACTION_PLAY_TEXT.to_string(), Some(vec![text]), did).await
 }
 
-/// Get device property
+/// Resume playback
 #[tauri::command]
-pub fn get_device_prop(prop: String) -> Result<serde_json::Value, String> {
-    let (api_base, api_key) = get_config()?;
-    let client = get_client()?;
+pub async fn playback_play(did: Option<String>) -> Result<MijiaActionResponse, PulseError> {
+    execute_device_action(ACTION_PLAY.to_string(), None, did).await
+}
 
-    let url = format!("{}/api/devices/{}/props/{}", api_base, SPEAKER_DEVICE_ID, prop);
+/// Pause playback
+#[tauri::command]
+pub async fn playback_pause(did: Option<String>) -> Result<MijiaActionResponse, PulseError> {
+    execute_device_action(ACTION_PAUSE.to_string(), None, did).await
+}
 
-    let response = client
-        .get(&url)
-        .header("X-API-Key", api_key)
-        .send()
-        .map_err(|e| format!("Request failed: {}", e))?;
+/// Skip to the next track
+#[tauri::command]
+pub async fn playback_next(did: Option<String>) -> Result<MijiaActionResponse, PulseError> {
+    execute_device_action(ACTION_NEXT.to_string(), None, did).await
+}
 
-    if !response.status().is_success() {
-        return Err(format!("API error: {}", response.status()));
-    }
+/// Go back to the previous track
+#[tauri::command]
+pub async fn playback_prev(did: Option<String>) -> Result<MijiaActionResponse, PulseError> {
+    execute_device_action(ACTION_PREV.to_string(), None, did).await
+}
+
+/// Get device property
+#[tauri::command]
+pub async fn get_device_prop(prop: String, did: Option<String>) -> Result<serde_json::Value, PulseError> {
+    let config = get_config()?;
+    let did = resolve_did(did, &config)?;
 
-    let text = response.text().map_err(|e| format!("Read response error: {}", e))?;
-    serde_json::from_str::<serde_json::Value>(&text).map_err(|e| format!("Parse error: {}", e))
+    send_request(&config, reqwest::Method::GET, &format!("/api/devices/{}/props/{}", did, prop), None).await
 }
 
 /// Set device property
 #[tauri::command]
-pub fn set_device_prop(prop: String, value: serde_json::Value) -> Result<serde_json::Value, String> {
-    let (api_base, api_key) = get_config()?;
-    let client = get_client()?;
-
-    let url = format!("{}/api/devices/{}/props/{}", api_base, SPEAKER_DEVICE_ID, prop);
+pub async fn set_device_prop(prop: String, value: serde_json::Value, did: Option<String>) -> Result<serde_json::Value, PulseError> {
+    let config = get_config()?;
+    let did = resolve_did(did, &config)?;
 
     let request_body = MijiaSetPropRequest { value };
 
-    let body = serde_json::to_string(&request_body).map_err(|e| format!("JSON encode error: {}", e))?;
+    let body = serde_json::to_string(&request_body).map_err(|e| PulseError::Encode(format!("JSON encode error: {}", e)))?;
 
-    let response = client
-        .put(&url)
-        .header("X-API-Key", api_key)
-        .header("Content-Type", "application/json")
-        .body(body)
-        .send()
-        .map_err(|e| format!("Request failed: {}", e))?;
+    send_request(&config, reqwest::Method::PUT, &format!("/api/devices/{}/props/{}", did, prop), Some(body)).await
+}
 
-    if !response.status().is_success() {
-        return Err(format!("API error: {}", response.status()));
-    }
+/// Get playback state
+#[tauri::command]
+pub async fn get_playback_state(did: Option<String>) -> Result<String, PulseError> {
+    let config = get_config()?;
+    let did = resolve_did(did, &config)?;
 
-    let text = response.text().map_err(|e| format!("Read response error: {}", e))?;
-    serde_json::from_str::<serde_json::Value>(&text).map_err(|e| format!("Parse error: {}", e))
+    let data = send_request(&config, reqwest::Method::GET, &format!("/api/devices/{}/playback-state", did), None).await?;
+    data["state"]
+        .as_str()
+        .ok_or_else(|| PulseError::Parse("Missing state field".to_string()))
+        .map(|s: &str| s.to_string())
 }
 
-/// Get playback state
+/// List devices known to the gateway
 #[tauri::command]
-pub fn get_playback_state() -> Result<String, String> {
-    let (api_base, api_key) = get_config()?;
-    let client = get_client()?;
+pub async fn list_devices() -> Result<serde_json::Value, PulseError> {
+    let config = get_config()?;
+
+    send_request(&config, reqwest::Method::GET, "/api/devices", None).await
+}
+
+/// Device lists change rarely; the UI may ask on every panel open
+const DEVICE_LIST_TTL: Duration = Duration::from_secs(60);
+
+static CACHED_DEVICES: RwLock<Option<(Instant, Vec<MijiaDevice>)>> = RwLock::new(None);
 
-    let url = format!("{}/api/devices/{}/playback-state", api_base, SPEAKER_DEVICE_ID);
+/// The gateway's devices, cached for DEVICE_LIST_TTL. An empty list is a
+/// successful result; a rejected API key is an `Api` error.
+#[tauri::command]
+pub async fn list_mijia_devices() -> Result<Vec<MijiaDevice>, PulseError> {
+    if let Some((fetched, devices)) = CACHED_DEVICES.read().unwrap().as_ref() {
+        if fetched.elapsed() < DEVICE_LIST_TTL {
+            return Ok(devices.clone());
+        }
+    }
+
+    let data = list_devices().await.map_err(|e| match e {
+        PulseError::Api { status: status @ (401 | 403), raw_response, .. } => PulseError::Api {
+            status,
+            message: "Mijia API key was rejected; check [mijia] api_key".to_string(),
+            raw_response,
+        },
+        e => e,
+    })?;
+
+    // Either a bare array or wrapped as {"devices": [...]}
+    let list = data.get("devices").cloned().unwrap_or(data);
+    let devices: Vec<MijiaDevice> = serde_json::from_value(list).map_err(|e| PulseError::Parse(format!("Unexpected device list: {}", e)))?;
+
+    *CACHED_DEVICES.write().unwrap() = Some((Instant::now(), devices.clone()));
+    Ok(devices)
+}
+
+/// Polling faster than this would hammer the gateway
+const MIN_PLAYBACK_POLL: Duration = Duration::from_secs(2);
 
-    let response = client
-        .get(&url)
-        .header("X-API-Key", api_key)
-        .send()
-        .map_err(|e| format!("Request failed: {}", e))?;
+#[derive(Debug, Clone, Serialize)]
+struct PlaybackEvent {
+    state: String,
+}
 
-    if !response.status().is_success() {
-        return Err(format!("API error: {}", response.status()));
+/// Poll the default device's playback state and emit `mijia-playback` when it
+/// changes. A new state must show up on two polls in a row before it's
+/// reported, so short blips like "buffering" between tracks are skipped.
+pub fn start_playback_poller(app: AppHandle, config: &MijiaConfig) {
+    if !config.poll_playback || config.api_base.is_empty() || config.default_did.is_empty() {
+        return;
     }
+    let interval = Duration::from_secs(config.playback_poll_secs).max(MIN_PLAYBACK_POLL);
+    log::info!("Polling Mijia playback state every {:?}", interval);
+
+    tauri::async_runtime::spawn(async move {
+        let mut reported: Option<String> = None;
+        let mut pending: Option<String> = None;
+        let mut last_error: Option<String> = None;
+
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let state = match get_playback_state(None).await {
+                Ok(state) => {
+                    last_error = None;
+                    state
+                }
+                Err(e) => {
+                    // Log once per distinct error rather than every poll while the gateway is down
+                    let message = e.to_string();
+                    if last_error.as_deref() != Some(message.as_str()) {
+                        log::warn!("Mijia playback poll failed: {}", message);
+                        last_error = Some(message);
+                    }
+                    continue;
+                }
+            };
+
+            if reported.as_deref() == Some(state.as_str()) {
+                pending = None;
+            } else if pending.as_deref() == Some(state.as_str()) {
+                let _ = app.emit("mijia-playback", PlaybackEvent { state: state.clone() });
+                reported = Some(state);
+                pending = None;
+            } else {
+                pending = Some(state);
+            }
+        }
+    });
+}
 
-    let text = response.text().map_err(|e| format!("Read response error: {}", e))?;
-    let data: serde_json::Value = serde_json::from_str(&text).map_err(|e| format!("Parse error: {}", e))?;
-    data["state"]
-        .as_str()
-        .ok_or_else(|| "Missing state field".to_string())
-        .map(|s: &str| s.to_string())
+const VOLUME_PROP: &str = "volume";
+const MAX_VOLUME: u8 = 100;
+
+/// Clamp a requested level into 0-100; signed so out-of-range UI input
+/// (e.g. a slider overshooting below zero) clamps instead of failing to deserialize
+fn clamp_volume(level: i64) -> u8 {
+    level.clamp(0, MAX_VOLUME as i64) as u8
+}
+
+/// Get speaker volume (0-100)
+#[tauri::command]
+pub async fn get_volume(did: Option<String>) -> Result<u8, PulseError> {
+    let data = get_device_prop(VOLUME_PROP.to_string(), did).await?;
+    data["value"]
+        .as_u64()
+        .map(|v| v.min(MAX_VOLUME as u64) as u8)
+        .ok_or_else(|| PulseError::Parse("Missing volume value".to_string()))
+}
+
+/// Set speaker volume, clamped to 0-100; returns the level applied
+#[tauri::command]
+pub async fn set_volume(level: i64, did: Option<String>) -> Result<u8, PulseError> {
+    let level = clamp_volume(level);
+    set_device_prop(VOLUME_PROP.to_string(), serde_json::json!(level), did).await?;
+    Ok(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_volume_bounds() {
+        assert_eq!(clamp_volume(-5), 0);
+        assert_eq!(clamp_volume(0), 0);
+        assert_eq!(clamp_volume(42), 42);
+        assert_eq!(clamp_volume(100), 100);
+        assert_eq!(clamp_volume(250), 100);
+    }
 }
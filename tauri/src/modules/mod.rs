@@ -6,26 +6,70 @@ pub mod utils;
 pub mod git;
 pub mod config;
 pub mod mijia;
+pub mod data_usage;
+pub mod history;
+pub mod shortcuts;
+pub mod error;
+pub mod watcher;
+pub mod autostart;
+pub mod logging;
+pub mod diagnostics;
+pub mod window_state;
+pub mod metrics;
 
 // Shared types and state
-use std::sync::Mutex;
-use sysinfo::{System, Networks};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::time::Instant;
+use sysinfo::{Disks, System, Networks};
+#[cfg(target_os = "macos")]
 use objc2::rc::Retained;
+use tauri::Wry;
+use tauri::menu::{CheckMenuItem, MenuItem};
+#[cfg(target_os = "macos")]
 use objc2_app_kit::NSStatusItem;
 
 // Wrapper for Thread Safety
+#[cfg(target_os = "macos")]
 pub struct ThreadSafeStatusItem(pub Retained<NSStatusItem>);
+#[cfg(target_os = "macos")]
 unsafe impl Send for ThreadSafeStatusItem {}
+#[cfg(target_os = "macos")]
 unsafe impl Sync for ThreadSafeStatusItem {}
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, Clone, Debug)]
 pub struct SystemStats {
     pub cpu_usage: f32,
+    /// GPU active residency in percent; None without root (powermetrics)
+    pub gpu_usage: Option<f32>,
     pub memory_used: u64,
     pub memory_total: u64,
     pub disk_usage_percent: u64,
     pub network_speed_up: u64,
     pub network_speed_down: u64,
+    /// Disk throughput aggregated over all processes; 0 on the first call after launch
+    pub disk_read_bps: u64,
+    pub disk_write_bps: u64,
+}
+
+/// One tray loop tick kept for get_stats_history
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct StatsSample {
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    pub cpu_usage: f32,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    pub network_speed_up: u64,
+    pub network_speed_down: u64,
+}
+
+/// Cumulative process disk counters from the previous get_system_stats call
+pub struct DiskIoSample {
+    pub at: Instant,
+    pub read_bytes: u64,
+    pub written_bytes: u64,
 }
 
 #[derive(serde::Serialize, Clone, Debug)]
@@ -35,19 +79,270 @@ pub struct UploadResult {
     pub filename: Option<String>,
     pub size: Option<String>,
     pub duration: Option<String>,
+    /// Average throughput of a successful upload, e.g. "3.2 MB/s"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_speed: Option<String>,
+    pub error: Option<String>,
+    /// Server response body, truncated, for diagnosing unexpected responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_response: Option<String>,
+    /// Same content was uploaded before; `url` is the earlier upload's
+    pub duplicate: bool,
+    /// SHA-256 of the uploaded bytes, stored in history for duplicate detection
+    #[serde(skip)]
+    pub sha256: Option<String>,
+}
+
+/// Minimal `upload-result` payload for lightweight consumers
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct UploadResultMinimal {
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
+impl From<UploadResult> for UploadResultMinimal {
+    fn from(result: UploadResult) -> Self {
+        Self {
+            url: result.url,
+            error: result.error,
+        }
+    }
+}
+
+/// `upload-result` payload: a full or minimal result, plus the file's position
+/// when it is one file of a multi-file upload
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct IndexedUploadResult<T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    #[serde(flatten)]
+    pub result: T,
+}
+
+/// Screen rectangle in points, origin at the top-left of the main display
+#[derive(serde::Deserialize, Clone, Copy, Debug)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(serde::Serialize)]
+pub struct UploadTestResult {
+    pub success: bool,
+    /// HTTP status of a failed response
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+    pub url: Option<String>,
+    pub message: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct UploadBackendInfo {
+    pub name: String,
+    pub url: String,
+    pub is_default: bool,
+}
+
 #[derive(serde::Serialize)]
 pub struct ClipboardImage {
     pub has_image: bool,
     pub data_url: Option<String>,
     pub size_bytes: Option<usize>,
+    /// "gif" for raw GIF passthrough, "png" otherwise
+    pub format: Option<String>,
     pub error: Option<String>,
 }
 
+#[derive(serde::Serialize)]
+pub struct ClipboardBenchmark {
+    pub iterations: u32,
+    pub has_image: bool,
+    pub min_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(serde::Serialize)]
+pub struct LoginItems {
+    pub items: Vec<String>,
+    pub note: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct GpuProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub gpu_percent: f32,
+}
+
+#[derive(serde::Serialize)]
+pub struct GpuProcesses {
+    pub processes: Vec<GpuProcessInfo>,
+    pub note: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ZombieProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub parent_pid: Option<u32>,
+    pub parent_name: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ZombieProcesses {
+    pub processes: Vec<ZombieProcessInfo>,
+    pub note: Option<String>,
+}
+
+/// Cumulative per-interface (transmitted, received) totals from the previous
+/// get_network_interfaces call
+pub struct InterfaceSample {
+    pub at: Instant,
+    pub totals: HashMap<String, (u64, u64)>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct NetworkInterfaceInfo {
+    pub name: String,
+    pub up_bps: u64,
+    pub down_bps: u64,
+    pub total_transmitted: u64,
+    pub total_received: u64,
+    pub is_default_route: bool,
+}
+
+/// Memory usage by category in bytes, for an Activity Monitor style chart
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct MemoryBreakdown {
+    pub total: u64,
+    /// Anonymous minus purgeable pages ("App Memory")
+    pub app: u64,
+    pub wired: u64,
+    pub compressed: u64,
+    /// File-backed and purgeable pages the system can reclaim
+    pub cached: u64,
+    pub free: u64,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct ProcessNetInfo {
+    pub pid: u32,
+    pub name: String,
+    pub up_bps: u64,
+    pub down_bps: u64,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct FanInfo {
+    pub index: usize,
+    pub current_rpm: u32,
+    /// Not reported by powermetrics
+    pub max_rpm: Option<u32>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct LoadAverage {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct SystemLoad {
+    pub uptime_secs: u64,
+    /// Humanized uptime, e.g. "3d 4h"
+    pub uptime: String,
+    /// None where the platform has no load average (Windows)
+    pub load_average: Option<LoadAverage>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct DiskInfo {
+    pub mount_point: String,
+    pub file_system: String,
+    pub total: u64,
+    pub available: u64,
+    pub usage_percent: f64,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct LocaleInfo {
+    pub identifier: String,
+    pub decimal_separator: char,
+}
+
+/// Support dump for bug reports. Only booleans and counts describe the
+/// upload/Mijia setup, so tokens and API keys can't end up in it.
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct Diagnostics {
+    pub app_version: String,
+    pub os_version: Option<String>,
+    pub config_path: String,
+    pub config_exists: bool,
+    pub config_errors: usize,
+    pub upload_configured: bool,
+    pub upload_backends: usize,
+    pub mijia_configured: bool,
+    pub proxy_configured: bool,
+    pub log_path: String,
+    /// Errors logged since launch
+    pub error_count: u64,
+}
+
+/// Disabled tray menu items showing live stats, updated by the tray loop
+pub struct TrayStatsItems {
+    pub cpu: MenuItem<Wry>,
+    pub memory: MenuItem<Wry>,
+    pub network: MenuItem<Wry>,
+}
+
+/// Uncolored text rendered around the menu-bar metrics
+#[derive(serde::Serialize, Clone, Debug, Default)]
+pub struct TrayLabel {
+    pub prefix: String,
+    pub suffix: String,
+}
+
 pub struct AppState {
     pub sys: Mutex<System>,
     pub networks: Mutex<Networks>,
+    /// When `networks` was last refreshed by get_system_stats
+    pub last_network_refresh: Mutex<Instant>,
+    pub last_disk_io: Mutex<Option<DiskIoSample>>,
+    /// Volumes for get_system_stats' disk usage, listed once and refreshed in place
+    pub disks: Mutex<Disks>,
+    /// `disk.mount_point`, kept current by the config watcher
+    pub disk_mount: Mutex<String>,
+    pub last_interface_sample: Mutex<Option<InterfaceSample>>,
+    #[cfg(target_os = "macos")]
     pub status_item: Mutex<Option<ThreadSafeStatusItem>>,
+    pub data_usage: Mutex<data_usage::DailyDataUsage>,
+    pub tray_label: Mutex<TrayLabel>,
+    pub display_mode: Mutex<config::DisplayMode>,
+    /// Set by the config watcher; the tray loop reloads its settings when it sees it
+    pub config_reloaded: AtomicBool,
+    /// Cancel flag of each running upload by id; cancel_upload sets one and
+    /// that upload's body reader checks it
+    pub upload_cancels: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    /// Uploads currently running; the tray loop stays at full speed while any are
+    pub uploads_in_flight: Arc<AtomicUsize>,
+    /// Whether the main window is shown, kept by the window_state show/hide helpers
+    pub window_visible: AtomicBool,
+    /// Freezes the menu bar text and icon; the tray loop keeps sampling
+    pub monitoring_paused: AtomicBool,
+    /// Last tray loop sample, served by the metrics endpoint when enabled
+    pub latest_stats: Mutex<Option<SystemStats>>,
+    /// Recent tray loop samples, oldest first, capped at system::STATS_HISTORY_LEN
+    pub stats_history: Mutex<VecDeque<StatsSample>>,
+    /// Tray "Launch at Login" item, set once the tray menu is built
+    pub autostart_item: Mutex<Option<CheckMenuItem<Wry>>>,
+    /// Tray "Pause Monitoring" item, kept in sync with `monitoring_paused`
+    pub pause_item: Mutex<Option<CheckMenuItem<Wry>>>,
+    pub stats_items: Mutex<Option<TrayStatsItems>>,
+    /// Tray "Copy Last URL" item, enabled while upload history is non-empty
+    pub copy_url_item: Mutex<Option<MenuItem<Wry>>>,
 }
@@ -0,0 +1,22 @@
+use std::str::FromStr;
+use tauri_plugin_global_shortcut::Shortcut;
+
+pub const DEFAULT_UPLOAD_SHORTCUT: &str = "Shift+Cmd+U";
+pub const DEFAULT_TOGGLE_WINDOW_SHORTCUT: &str = "Shift+Cmd+P";
+pub const DEFAULT_CAPTURE_SHORTCUT: &str = "Shift+Cmd+X";
+
+/// Parse a shortcut string like "Shift+Cmd+U" into modifiers and key code
+pub fn parse_shortcut(shortcut: &str) -> Result<Shortcut, String> {
+    Shortcut::from_str(shortcut).map_err(|e| format!("Invalid shortcut '{}': {}", shortcut, e))
+}
+
+/// Parse a configured shortcut, falling back to `default` with a warning on failure
+pub fn parse_shortcut_or_default(shortcut: &str, default: &str) -> Shortcut {
+    match parse_shortcut(shortcut) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            log::warn!("{}, falling back to {}", e, default);
+            parse_shortcut(default).expect("default shortcut must parse")
+        }
+    }
+}
@@ -1,17 +1,35 @@
-use tauri::{State, AppHandle};
-use sysinfo::{System, Disks, Networks};
-use crate::modules::{SystemStats, AppState};
+use tauri::{State, AppHandle, Emitter, Manager};
+use sysinfo::{Components, System, DiskRefreshKind, Disks, Networks, ProcessStatus, ProcessesToUpdate};
+use crate::modules::{SystemStats, AppState, LoginItems, LocaleInfo, GpuProcessInfo, GpuProcesses, ZombieProcessInfo, ZombieProcesses, DiskInfo, DiskIoSample, LoadAverage, SystemLoad, FanInfo, ProcessNetInfo, InterfaceSample, MemoryBreakdown, StatsSample, NetworkInterfaceInfo};
+use crate::modules::error::PulseError;
+use crate::modules::utils::{ema, current_locale, format_uptime, FormatOptions, LockExt};
+use crate::modules::data_usage::record_data_usage;
+use crate::modules::config::TrayConfig;
+use crate::modules::tray::TrayReading;
+use std::collections::{HashMap, VecDeque};
 use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
-#[cfg(target_os = "macos")]
-fn get_macos_memory_usage() -> Option<u64> {
-    let output = Command::new("vm_stat").output().ok()?;
-    let output_str = String::from_utf8_lossy(&output.stdout);
+/// Page counts from `vm_stat`, converted to bytes
+#[cfg(any(target_os = "macos", test))]
+#[derive(Debug, Default)]
+struct VmStat {
+    free: u64,
+    wired: u64,
+    compressed: u64,
+    file_backed: u64,
+    anonymous: u64,
+    purgeable: u64,
+}
 
+#[cfg(any(target_os = "macos", test))]
+fn parse_vm_stat(output: &str) -> VmStat {
     // Default to 16KB for Apple Silicon, fallback to 4KB if unknown
     // We try to parse the header "Mach Virtual Memory Statistics: (page size of 16384 bytes)"
     let mut page_size = 16384;
-    if let Some(first_line) = output_str.lines().next() {
+    if let Some(first_line) = output.lines().next() {
         if let Some(start) = first_line.find("page size of ") {
             if let Some(end) = first_line[start..].find(" bytes") {
                 if let Ok(size) = first_line[start + 13..start + end].parse::<u64>() {
@@ -21,97 +39,921 @@ fn get_macos_memory_usage() -> Option<u64> {
         }
     }
 
-    let mut pages_anonymous = 0;
-    let mut pages_purgeable = 0;
+    let mut stat = VmStat::default();
+    for line in output.lines() {
+        let Some((label, value)) = line.split_once(':') else {
+            continue;
+        };
+        let pages = value.trim().trim_end_matches('.').parse::<u64>().unwrap_or(0);
+        let field = match label {
+            "Pages free" => &mut stat.free,
+            "Pages wired down" => &mut stat.wired,
+            "Pages occupied by compressor" => &mut stat.compressed,
+            "File-backed pages" => &mut stat.file_backed,
+            "Anonymous pages" => &mut stat.anonymous,
+            "Pages purgeable" => &mut stat.purgeable,
+            _ => continue,
+        };
+        *field = pages * page_size;
+    }
+    stat
+}
 
-    for line in output_str.lines() {
-        if line.starts_with("Anonymous pages:") {
-            if let Some(val) = line.split(':').nth(1) {
-                pages_anonymous = val.trim().trim_end_matches('.').parse::<u64>().unwrap_or(0);
-            }
-        } else if line.starts_with("Pages purgeable:") {
-             if let Some(val) = line.split(':').nth(1) {
-                pages_purgeable = val.trim().trim_end_matches('.').parse::<u64>().unwrap_or(0);
-            }
-        }
+#[cfg(any(target_os = "macos", test))]
+impl VmStat {
+    /// "App Memory" calculation matching Activity Monitor
+    /// App Memory = (Anonymous pages - Purgeable pages) * Page Size
+    /// This represents the physical memory used by user-space apps
+    fn app_memory(&self) -> u64 {
+        self.anonymous.saturating_sub(self.purgeable)
     }
+}
+
+#[cfg(target_os = "macos")]
+fn read_vm_stat() -> Option<VmStat> {
+    let output = Command::new("vm_stat").output().ok()?;
+    Some(parse_vm_stat(&String::from_utf8_lossy(&output.stdout)))
+}
 
-    // "App Memory" calculation matching Activity Monitor
-    // App Memory = (Anonymous pages - Purgeable pages) * Page Size
-    // This represents the physical memory used by user-space apps
-    let used_bytes = (pages_anonymous.saturating_sub(pages_purgeable)) * page_size;
-    Some(used_bytes)
+#[cfg(target_os = "macos")]
+fn get_macos_memory_usage() -> Option<u64> {
+    read_vm_stat().map(|stat| stat.app_memory())
+}
+
+/// Used memory; on macOS the Activity Monitor "App Memory" figure, sysinfo elsewhere
+fn memory_used(sys: &System) -> u64 {
+    #[cfg(target_os = "macos")]
+    if let Some(used) = get_macos_memory_usage() {
+        return used;
+    }
+    sys.used_memory()
+}
+
+/// Memory split like Activity Monitor's memory tab, in bytes. Outside macOS
+/// only `app` (sysinfo's used memory) and `free` are filled in.
+#[tauri::command]
+pub fn get_memory_breakdown(state: State<AppState>) -> MemoryBreakdown {
+    let mut sys = state.sys.lock_or_recover();
+    sys.refresh_memory();
+    let total = sys.total_memory();
+
+    #[cfg(target_os = "macos")]
+    if let Some(stat) = read_vm_stat() {
+        return MemoryBreakdown {
+            total,
+            app: stat.app_memory(),
+            wired: stat.wired,
+            compressed: stat.compressed,
+            cached: stat.file_backed + stat.purgeable,
+            free: stat.free,
+        };
+    }
+
+    MemoryBreakdown {
+        total,
+        app: sys.used_memory(),
+        wired: 0,
+        compressed: 0,
+        cached: 0,
+        free: sys.free_memory(),
+    }
+}
+
+/// Last mount point reported missing, so the per-tick callers warn once per
+/// configured value instead of on every refresh
+static MISSING_MOUNT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Used-space percentage of the volume mounted at `mount_point`, falling back
+/// to `/` when that mount isn't present (e.g. an unplugged external drive).
+/// Only that volume's space is refreshed; `disks` is re-listed when the mount
+/// isn't in it.
+fn disk_usage_percent(disks: &mut Disks, mount_point: &str) -> u64 {
+    let position = |disks: &Disks, mount: &str| disks.iter().position(|disk| disk.mount_point().to_string_lossy() == mount);
+
+    let index = match position(disks, mount_point) {
+        Some(index) => Some(index),
+        None => {
+            // Pick up volumes mounted since the list was built
+            disks.refresh(true);
+            position(disks, mount_point).or_else(|| {
+                let mut missing = MISSING_MOUNT.lock_or_recover();
+                if missing.as_deref() != Some(mount_point) {
+                    log::warn!("Mount point '{}' not found, reporting / instead", mount_point);
+                    *missing = Some(mount_point.to_string());
+                }
+                position(disks, "/")
+            })
+        }
+    };
+
+    let Some(disk) = index.map(|index| &mut disks[index]) else {
+        return 0;
+    };
+    disk.refresh_specifics(DiskRefreshKind::nothing().with_storage());
+    let total = disk.total_space();
+    if total == 0 {
+        return 0;
+    }
+    ((total - disk.available_space()) as f64 / total as f64 * 100.0) as u64
 }
 
 #[tauri::command]
 pub fn get_system_stats(state: State<AppState>) -> SystemStats {
-    let mut sys = state.sys.lock().unwrap();
-    let mut networks = state.networks.lock().unwrap();
+    let stats = sample_system_stats(&state);
+    // powermetrics takes ~200ms, so it runs after the sys/networks locks are
+    // released rather than stalling the tray loop and other stats commands
+    SystemStats { gpu_usage: sample_gpu_usage(), ..stats }
+}
+
+/// Everything in SystemStats except the GPU, sampled under the sys/networks locks
+fn sample_system_stats(state: &AppState) -> SystemStats {
+    let mut sys = state.sys.lock_or_recover();
+    let mut networks = state.networks.lock_or_recover();
 
     sys.refresh_all();
     networks.refresh(true);
 
+    // Network counters are deltas since the previous refresh; callers poll at
+    // arbitrary intervals, so divide by the real elapsed time
+    let now = Instant::now();
+    let elapsed_secs = {
+        let mut last_refresh = state.last_network_refresh.lock_or_recover();
+        let elapsed = now.duration_since(*last_refresh).as_secs_f64();
+        *last_refresh = now;
+        elapsed
+    };
+
     let cpu_usage = sys.global_cpu_usage();
     let memory_total = sys.total_memory();
+    let memory_used = memory_used(&sys);
+    let disk_usage_percent = {
+        let mount_point = state.disk_mount.lock_or_recover().clone();
+        disk_usage_percent(&mut state.disks.lock_or_recover(), &mount_point)
+    };
 
-    // Use platform-specific calculation for macOS, fallback to sysinfo for others
-    #[cfg(target_os = "macos")]
-    let memory_used = get_macos_memory_usage().unwrap_or_else(|| sys.used_memory());
+    let mut transmitted: u64 = 0;
+    let mut received: u64 = 0;
+    for (_name, network) in &*networks {
+        transmitted += network.transmitted();
+        received += network.received();
+    }
+    let per_second = |bytes: u64| if elapsed_secs > 0.0 { (bytes as f64 / elapsed_secs) as u64 } else { 0 };
+    let network_speed_up = per_second(transmitted);
+    let network_speed_down = per_second(received);
+
+    let (disk_read_bps, disk_write_bps) = disk_io_rates(&sys, state, now);
+
+    SystemStats {
+        cpu_usage,
+        gpu_usage: None,
+        memory_used,
+        memory_total,
+        disk_usage_percent,
+        network_speed_up,
+        network_speed_down,
+        disk_read_bps,
+        disk_write_bps,
+    }
+}
+
+/// Disk read/write bytes per second since the previous call, from cumulative
+/// per-process counters. The first call has no baseline and reports 0.
+fn disk_io_rates(sys: &System, state: &AppState, now: Instant) -> (u64, u64) {
+    let (read_bytes, written_bytes) = sys.processes().values().fold((0u64, 0u64), |(read, written), process| {
+        let usage = process.disk_usage();
+        (read + usage.total_read_bytes, written + usage.total_written_bytes)
+    });
 
-    #[cfg(not(target_os = "macos"))]
-    let memory_used = sys.used_memory();
+    let mut last = state.last_disk_io.lock_or_recover();
+    let rates = match last.as_ref() {
+        Some(prev) => {
+            let secs = now.duration_since(prev.at).as_secs_f64();
+            if secs > 0.0 {
+                // Exited processes drop out of the totals, so never go negative
+                (
+                    (read_bytes.saturating_sub(prev.read_bytes) as f64 / secs) as u64,
+                    (written_bytes.saturating_sub(prev.written_bytes) as f64 / secs) as u64,
+                )
+            } else {
+                (0, 0)
+            }
+        }
+        None => (0, 0),
+    };
+    *last = Some(DiskIoSample { at: now, read_bytes, written_bytes });
+    rates
+}
+
+/// Uptime and 1/5/15-minute load averages
+#[tauri::command]
+pub fn get_system_load() -> SystemLoad {
+    let uptime_secs = System::uptime();
+    let load_average = if cfg!(target_os = "windows") {
+        None
+    } else {
+        let load = System::load_average();
+        Some(LoadAverage { one: load.one, five: load.five, fifteen: load.fifteen })
+    };
+
+    SystemLoad {
+        uptime_secs,
+        uptime: format_uptime(uptime_secs),
+        load_average,
+    }
+}
+
+/// macOS mounts the sealed system snapshot at "/" and its companion volumes
+/// (Data, VM, Preboot, ...) under /System/Volumes; they share one APFS
+/// container, so only "/" is reported for it
+fn is_hidden_volume(mount_point: &str, read_only: bool) -> bool {
+    if mount_point == "/" {
+        return false;
+    }
+    read_only
+        || mount_point.starts_with("/System/Volumes/")
+        || mount_point.contains("com.apple.TimeMachine.localsnapshots")
+        || mount_point.starts_with("/private/var/vm")
+}
 
+/// Usage of every mounted disk, skipping read-only system volumes and snapshots
+#[tauri::command]
+pub fn get_disk_usage() -> Vec<DiskInfo> {
     let disks = Disks::new_with_refreshed_list();
-    let mut disk_usage_percent = 0;
-    for disk in &disks {
-        if disk.mount_point().to_string_lossy() == "/" {
-             let total = disk.total_space();
-             let available = disk.available_space();
-             if total > 0 {
-                 disk_usage_percent = ((total - available) as f64 / total as f64 * 100.0) as u64;
-             }
-             break;
+    disks
+        .iter()
+        .filter(|disk| !is_hidden_volume(&disk.mount_point().to_string_lossy(), disk.is_read_only()))
+        .map(|disk| {
+            let total = disk.total_space();
+            let available = disk.available_space();
+            let usage_percent = if total > 0 {
+                (total - available) as f64 / total as f64 * 100.0
+            } else {
+                0.0
+            };
+            DiskInfo {
+                mount_point: disk.mount_point().to_string_lossy().to_string(),
+                file_system: disk.file_system().to_string_lossy().to_string(),
+                total,
+                available,
+                usage_percent,
+            }
+        })
+        .collect()
+}
+
+/// List user login items via System Events (read-only)
+#[tauri::command]
+pub fn get_login_items() -> LoginItems {
+    let output = Command::new("osascript")
+        .args(["-e", "tell application \"System Events\" to get the name of every login item"])
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            // osascript prints the list as "Item A, Item B, Item C"
+            let items = stdout
+                .trim()
+                .split(", ")
+                .filter(|name| !name.is_empty())
+                .map(|name| name.to_string())
+                .collect();
+            LoginItems { items, note: None }
+        }
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            log::warn!("Failed to read login items: {}", stderr.trim());
+            LoginItems {
+                items: Vec::new(),
+                note: Some(format!("Login items not accessible (Automation permission may be required): {}", stderr.trim())),
+            }
+        }
+        Err(e) => {
+            log::warn!("Failed to execute osascript: {}", e);
+            LoginItems {
+                items: Vec::new(),
+                note: Some(format!("Failed to execute osascript: {}", e)),
+            }
         }
     }
+}
 
-    let mut network_speed_up: u64 = 0;
-    let mut network_speed_down: u64 = 0;
-    for (_name, network) in &*networks {
-        network_speed_up += network.transmitted();
-        network_speed_down += network.received();
+/// Parse the `powermetrics --samplers tasks --show-process-gpu` table.
+/// GPU time is reported in ms/s, so 1000 ms/s equals 100%.
+fn parse_powermetrics_gpu_tasks(output: &str) -> Vec<GpuProcessInfo> {
+    let mut processes = Vec::new();
+    let mut in_table = false;
+
+    for line in output.lines() {
+        if !in_table {
+            in_table = line.contains("GPU ms/s");
+            continue;
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+
+        // Process names may contain spaces, so the pid is the first numeric column
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some(pid_index) = tokens.iter().skip(1).position(|t| t.parse::<u32>().is_ok()).map(|i| i + 1) else {
+            continue;
+        };
+        let (Ok(pid), Some(Ok(gpu_ms))) = (
+            tokens[pid_index].parse::<u32>(),
+            tokens.last().map(|t| t.parse::<f32>()),
+        ) else {
+            continue;
+        };
+
+        processes.push(GpuProcessInfo {
+            pid,
+            name: tokens[..pid_index].join(" "),
+            gpu_percent: gpu_ms / 10.0,
+        });
     }
 
+    processes
+}
+
+const PROCESS_NETWORK_LIMIT: usize = 20;
+
+/// Parse the last sample of `nettop -P -L 2 -d -J bytes_in,bytes_out -x`.
+/// Each sample starts with a ",bytes_in,bytes_out," header, followed by
+/// "name.pid,in,out," rows; with -d the byte counts are deltas over the interval.
+fn parse_nettop(output: &str, interval_secs: u64) -> Vec<ProcessNetInfo> {
+    let last_sample = output.rsplit(",bytes_in,bytes_out,").next().unwrap_or_default();
+    last_sample
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',');
+            let (name, pid) = fields.next()?.rsplit_once('.')?;
+            let bytes_in: u64 = fields.next()?.trim().parse().ok()?;
+            let bytes_out: u64 = fields.next()?.trim().parse().ok()?;
+            Some(ProcessNetInfo {
+                pid: pid.parse().ok()?,
+                name: name.to_string(),
+                up_bps: bytes_out / interval_secs,
+                down_bps: bytes_in / interval_secs,
+            })
+        })
+        .collect()
+}
+
+/// Per-process network throughput via nettop (takes about a second to sample),
+/// busiest first. sysinfo has no per-process network counters.
+#[tauri::command]
+pub fn get_process_network(limit: Option<usize>) -> Vec<ProcessNetInfo> {
+    const INTERVAL_SECS: u64 = 1;
+    let output = Command::new("nettop")
+        .args(["-P", "-L", "2", "-d", "-s", &INTERVAL_SECS.to_string(), "-J", "bytes_in,bytes_out", "-x"])
+        .output();
+
+    let mut processes = match output {
+        Ok(output) if output.status.success() => parse_nettop(&String::from_utf8_lossy(&output.stdout), INTERVAL_SECS),
+        Ok(output) => {
+            log::warn!("nettop failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+            return Vec::new();
+        }
+        Err(e) => {
+            log::warn!("Per-process network usage unavailable, failed to execute nettop: {}", e);
+            return Vec::new();
+        }
+    };
+
+    processes.retain(|p| p.up_bps + p.down_bps > 0);
+    processes.sort_by_key(|p| std::cmp::Reverse(p.up_bps + p.down_bps));
+    processes.truncate(limit.unwrap_or(PROCESS_NETWORK_LIMIT));
+    processes
+}
+
+/// Interface carrying the default route, from `route -n get default`
+#[cfg(target_os = "macos")]
+fn default_route_interface() -> Option<String> {
+    let output = Command::new("route").args(["-n", "get", "default"]).output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("interface:"))
+        .map(|name| name.trim().to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn default_route_interface() -> Option<String> {
+    None
+}
+
+/// Per-interface network rates since the previous call, busiest first.
+/// Loopback ("lo*") is skipped unless `include_loopback` is set. The first
+/// call has no baseline and reports 0 rates.
+#[tauri::command]
+pub fn get_network_interfaces(state: State<AppState>, include_loopback: Option<bool>) -> Vec<NetworkInterfaceInfo> {
+    // A separate list so this doesn't reset the deltas get_system_stats relies on
+    let networks = Networks::new_with_refreshed_list();
+    let now = Instant::now();
+    let default_route = default_route_interface();
+    let include_loopback = include_loopback.unwrap_or(false);
+
+    let totals: HashMap<String, (u64, u64)> = networks
+        .iter()
+        .map(|(name, data)| (name.clone(), (data.total_transmitted(), data.total_received())))
+        .collect();
+
+    let mut last = state.last_interface_sample.lock_or_recover();
+    let previous = last.as_ref().map(|prev| (now.duration_since(prev.at).as_secs_f64(), &prev.totals));
+
+    let mut interfaces: Vec<NetworkInterfaceInfo> = totals
+        .iter()
+        .filter(|(name, _)| include_loopback || !name.starts_with("lo"))
+        .map(|(name, &(transmitted, received))| {
+            let (up_bps, down_bps) = match previous {
+                Some((secs, prev_totals)) if secs > 0.0 => match prev_totals.get(name) {
+                    Some(&(prev_tx, prev_rx)) => (
+                        (transmitted.saturating_sub(prev_tx) as f64 / secs) as u64,
+                        (received.saturating_sub(prev_rx) as f64 / secs) as u64,
+                    ),
+                    None => (0, 0),
+                },
+                _ => (0, 0),
+            };
+            NetworkInterfaceInfo {
+                name: name.clone(),
+                up_bps,
+                down_bps,
+                total_transmitted: transmitted,
+                total_received: received,
+                is_default_route: default_route.as_deref() == Some(name.as_str()),
+            }
+        })
+        .collect();
+
+    *last = Some(InterfaceSample { at: now, totals });
+    interfaces.sort_by(|a, b| (b.up_bps + b.down_bps).cmp(&(a.up_bps + a.down_bps)).then_with(|| a.name.cmp(&b.name)));
+    interfaces
+}
+
+/// Set once powermetrics has failed, so get_system_stats stops spawning it
+#[cfg(target_os = "macos")]
+static GPU_SAMPLING_UNAVAILABLE: AtomicBool = AtomicBool::new(false);
+
+/// Parse the GPU active residency from `powermetrics --samplers gpu_power`:
+/// "GPU HW active residency:  12.34% (...)" on Apple Silicon,
+/// "GPU active residency:  12.34%" on Intel
+#[cfg(target_os = "macos")]
+fn parse_powermetrics_gpu_usage(output: &str) -> Option<f32> {
+    output
+        .lines()
+        .find(|line| line.trim_start().starts_with("GPU") && line.contains("active residency:"))
+        .and_then(|line| line.split_once(':'))
+        .and_then(|(_, value)| value.split('%').next())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Overall GPU usage, or None when powermetrics can't run (it needs root).
+/// The failure is logged once and sampling is not retried.
+#[cfg(target_os = "macos")]
+fn sample_gpu_usage() -> Option<f32> {
+    if GPU_SAMPLING_UNAVAILABLE.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let output = Command::new("powermetrics")
+        .args(["--samplers", "gpu_power", "-n", "1", "-i", "200"])
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_powermetrics_gpu_usage(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(output) => {
+            if !GPU_SAMPLING_UNAVAILABLE.swap(true, Ordering::Relaxed) {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                log::warn!("GPU usage unavailable, powermetrics must run as root: {}", stderr.trim());
+            }
+            None
+        }
+        Err(e) => {
+            if !GPU_SAMPLING_UNAVAILABLE.swap(true, Ordering::Relaxed) {
+                log::warn!("GPU usage unavailable, failed to execute powermetrics: {}", e);
+            }
+            None
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn sample_gpu_usage() -> Option<f32> {
+    None
+}
+
+/// Parse "Fan: 1799.89 rpm" lines from `powermetrics --samplers smc`
+fn parse_powermetrics_fans(output: &str) -> Vec<FanInfo> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Fan:"))
+        .filter_map(|value| value.trim().trim_end_matches("rpm").trim().parse::<f32>().ok())
+        .enumerate()
+        .map(|(index, rpm)| FanInfo { index, current_rpm: rpm.round() as u32, max_rpm: None })
+        .collect()
+}
+
+/// Fan speeds on Intel Macs via powermetrics' SMC sampler.
+/// Apple Silicon doesn't expose fans there, so it returns an empty list.
+#[tauri::command]
+pub fn get_fan_speeds() -> Result<Vec<FanInfo>, PulseError> {
+    if cfg!(target_arch = "aarch64") {
+        return Ok(Vec::new());
+    }
+
+    let output = Command::new("powermetrics")
+        .args(["--samplers", "smc", "-n", "1", "-i", "200"])
+        .output()
+        .map_err(|e| PulseError::Io(format!("Failed to execute powermetrics: {}", e)))?;
+
+    if output.status.success() {
+        return Ok(parse_powermetrics_fans(&String::from_utf8_lossy(&output.stdout)));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("superuser") {
+        Err(PulseError::Permission(
+            "Reading fan speeds requires powermetrics to run as root; allow it via sudoers or run Pulse with sudo".to_string(),
+        ))
+    } else {
+        // e.g. "unrecognized sampler: smc" when the machine has no readable fans
+        log::info!("No fan readings from powermetrics: {}", stderr.trim());
+        Ok(Vec::new())
+    }
+}
+
+/// Rank processes by GPU usage (requires powermetrics, which needs root)
+#[tauri::command]
+pub fn get_top_gpu_processes(limit: usize) -> GpuProcesses {
+    let output = Command::new("powermetrics")
+        .args(["--samplers", "tasks", "--show-process-gpu", "-n", "1", "-i", "500"])
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut processes = parse_powermetrics_gpu_tasks(&stdout);
+            processes.retain(|p| p.gpu_percent > 0.0);
+            processes.sort_by(|a, b| b.gpu_percent.partial_cmp(&a.gpu_percent).unwrap_or(std::cmp::Ordering::Equal));
+            processes.truncate(limit);
+            GpuProcesses { processes, note: None }
+        }
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            log::warn!("powermetrics failed: {}", stderr.trim());
+            GpuProcesses {
+                processes: Vec::new(),
+                note: Some("Per-process GPU usage requires powermetrics to run as root".to_string()),
+            }
+        }
+        Err(e) => {
+            log::warn!("Failed to execute powermetrics: {}", e);
+            GpuProcesses {
+                processes: Vec::new(),
+                note: Some(format!("Failed to execute powermetrics: {}", e)),
+            }
+        }
+    }
+}
+
+/// List zombie (defunct) processes with their parents.
+/// Zombies can't be killed: they disappear once the parent reaps them,
+/// or once the parent exits and launchd adopts and reaps them.
+#[tauri::command]
+pub fn get_zombie_processes(state: State<AppState>) -> ZombieProcesses {
+    let mut sys = state.sys.lock_or_recover();
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+
+    let processes: Vec<ZombieProcessInfo> = sys
+        .processes()
+        .values()
+        .filter(|process| process.status() == ProcessStatus::Zombie)
+        .map(|process| {
+            let parent = process.parent();
+            ZombieProcessInfo {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string_lossy().to_string(),
+                parent_pid: parent.map(|pid| pid.as_u32()),
+                parent_name: parent
+                    .and_then(|pid| sys.process(pid))
+                    .map(|p| p.name().to_string_lossy().to_string()),
+            }
+        })
+        .collect();
+
+    let note = if processes.is_empty() {
+        None
+    } else {
+        Some("Zombies are reaped by their parent; restart or quit the parent process to clear them".to_string())
+    };
+
+    ZombieProcesses { processes, note }
+}
+
+/// Get the current macOS locale for number formatting
+#[tauri::command]
+pub fn get_locale() -> LocaleInfo {
+    current_locale()
+}
+
+const CPU_HISTORY_LEN: usize = 20;
+
+/// Samples kept for get_stats_history: 5 minutes at the default 1s refresh
+pub const STATS_HISTORY_LEN: usize = 300;
+
+fn record_stats_sample(state: &AppState, sample: StatsSample) {
+    let mut history = state.stats_history.lock_or_recover();
+    if history.len() == STATS_HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(sample);
+}
+
+/// Recent CPU, memory and network samples from the tray loop, oldest first,
+/// so the window can draw a full graph as soon as it opens
+#[tauri::command]
+pub fn get_stats_history(state: State<AppState>) -> Vec<StatsSample> {
+    state.stats_history.lock_or_recover().iter().cloned().collect()
+}
+
+/// Config-derived tray loop settings, rebuilt when config.toml changes
+struct TraySettings {
+    tray: TrayConfig,
+    data_cap_gb: Option<f64>,
+    format: FormatOptions,
+    emit_stats: bool,
+    metrics: bool,
+    disk_mount: String,
+}
+
+impl TraySettings {
+    fn load() -> Self {
+        let config = crate::modules::config::load_config();
+        Self {
+            format: FormatOptions::from_config(&config.general),
+            data_cap_gb: config.general.daily_data_cap_gb,
+            emit_stats: config.general.emit_stats,
+            metrics: config.metrics.enabled,
+            disk_mount: config.disk.mount_point,
+            tray: config.tray,
+        }
+    }
+}
+
+/// Hottest CPU sensor in °C. Intel Macs label it "CPU ..."; Apple Silicon
+/// exposes per-die "PMU tdie" sensors.
+fn cpu_temperature(components: &Components) -> Option<f32> {
+    components
+        .iter()
+        .filter(|component| {
+            let label = component.label().to_lowercase();
+            label.contains("cpu") || label.contains("tdie")
+        })
+        .filter_map(|component| component.temperature())
+        .filter(|temp| temp.is_finite() && *temp > 0.0)
+        .reduce(f32::max)
+}
+
+/// Tray refresh interval while the window is hidden and nothing is uploading
+const IDLE_REFRESH_INTERVAL: Duration = Duration::from_secs(3);
+
+fn main_window_visible(app: &AppHandle) -> bool {
+    app.state::<AppState>().window_visible.load(Ordering::Relaxed)
+}
+
+/// Hidden window and no upload running: only the menu bar needs updating
+fn is_idle(app: &AppHandle) -> bool {
+    !main_window_visible(app) && app.state::<AppState>().uploads_in_flight.load(Ordering::Relaxed) == 0
+}
+
+/// Tray loop thread, unparked by `request_tray_refresh`
+static TRAY_LOOP_THREAD: OnceLock<std::thread::Thread> = OnceLock::new();
+static REFRESH_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Wake the tray loop for a reading now instead of after its current sleep
+pub fn request_tray_refresh() {
+    REFRESH_REQUESTED.store(true, Ordering::Relaxed);
+    if let Some(thread) = TRAY_LOOP_THREAD.get() {
+        thread.unpark();
+    }
+}
+
+/// Sleep for the idle or normal interval. An idle sleep is taken in
+/// `interval`-sized steps and cut short as soon as the app becomes active;
+/// any sleep ends early on `request_tray_refresh`.
+fn sleep_tick(app: &AppHandle, interval: Duration) {
+    let idle = is_idle(app) && interval < IDLE_REFRESH_INTERVAL;
+    let duration = if idle { IDLE_REFRESH_INTERVAL } else { interval };
+    let started = Instant::now();
+    while let Some(remaining) = duration.checked_sub(started.elapsed()) {
+        std::thread::park_timeout(interval.min(remaining));
+        if REFRESH_REQUESTED.swap(false, Ordering::Relaxed) {
+            // CPU usage is meaningless below the minimum gap between refreshes
+            if let Some(rest) = sysinfo::MINIMUM_CPU_UPDATE_INTERVAL.checked_sub(started.elapsed()) {
+                std::thread::sleep(rest);
+            }
+            break;
+        }
+        if idle && !is_idle(app) {
+            break;
+        }
+    }
+}
+
+/// Stats from the tray loop's samples, for the `system-stats` event and the
+/// metrics endpoint. GPU and disk I/O need extra sampling and are left out (None / 0).
+fn tray_stats(cpu: f32, memory_used: u64, memory_total: u64, up: u64, down: u64, disks: &mut Disks, mount_point: &str) -> SystemStats {
     SystemStats {
-        cpu_usage,
+        cpu_usage: cpu,
+        gpu_usage: None,
         memory_used,
         memory_total,
-        disk_usage_percent,
-        network_speed_up,
-        network_speed_down,
+        disk_usage_percent: disk_usage_percent(disks, mount_point),
+        network_speed_up: up,
+        network_speed_down: down,
+        disk_read_bps: 0,
+        disk_write_bps: 0,
     }
 }
 
+/// CPU usage is a delta between two refreshes, so the first reading after
+/// `System::new_all()` is meaningless (often near 100%). Take a baseline
+/// before anything is displayed so the menu bar doesn't flash red at startup.
+fn warm_up_cpu(sys: &mut System) {
+    sys.refresh_cpu_all();
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_cpu_all();
+}
+
 pub fn start_tray_update_loop(app: AppHandle) {
     std::thread::spawn(move || {
+        let _ = TRAY_LOOP_THREAD.set(std::thread::current());
+        let mut settings = TraySettings::load();
+        let status_bar = crate::modules::tray::platform_status_bar();
+        let mut up_avg: Option<f64> = None;
+        let mut down_avg: Option<f64> = None;
         let mut sys = System::new_all();
+        warm_up_cpu(&mut sys);
         let mut networks = Networks::new_with_refreshed_list();
+        let mut disks = Disks::new_with_refreshed_list();
+        let mut components = Components::new_with_refreshed_list();
+        let mut cpu_history: VecDeque<f32> = VecDeque::with_capacity(CPU_HISTORY_LEN);
+
+        let mut last_tick = Instant::now();
+        let mut last_health = None;
 
         loop {
-            std::thread::sleep(std::time::Duration::from_secs(1));
+            sleep_tick(&app, settings.tray.refresh_interval());
+            let now = Instant::now();
+            let elapsed = now.duration_since(last_tick);
+            last_tick = now;
+
+            if app.state::<AppState>().config_reloaded.swap(false, Ordering::Relaxed) {
+                log::info!("Tray loop picking up reloaded config");
+                settings = TraySettings::load();
+            }
 
             sys.refresh_cpu_all();
             networks.refresh(true);
 
             let cpu = sys.global_cpu_usage();
+            if cpu_history.len() == CPU_HISTORY_LEN {
+                cpu_history.pop_front();
+            }
+            cpu_history.push_back(cpu);
 
             let mut up = 0;
             let mut down = 0;
-            for (_name, network) in &networks {
+            for (name, network) in &networks {
+                if !settings.tray.interface_filter.includes(name) {
+                    continue;
+                }
                 up += network.transmitted();
                 down += network.received();
             }
 
-            crate::modules::tray::update_status_bar(&app, cpu, up, down);
+            let over_data_cap = record_data_usage(&app, up, down, settings.data_cap_gb);
+
+            let mode = *app.state::<AppState>().display_mode.lock_or_recover();
+            sys.refresh_memory();
+            let memory_total = sys.total_memory();
+            let memory_used = memory_used(&sys);
+            let memory_percent = if memory_total > 0 {
+                memory_used as f32 / memory_total as f32 * 100.0
+            } else {
+                0.0
+            };
+
+            // Deltas are per refresh, normalize to bytes per second and smooth
+            let interval_secs = elapsed.as_secs_f64();
+            let alpha = settings.tray.smoothing_alpha();
+            let up_smoothed = ema(up_avg, up as f64 / interval_secs, alpha);
+            let down_smoothed = ema(down_avg, down as f64 / interval_secs, alpha);
+            up_avg = Some(up_smoothed);
+            down_avg = Some(down_smoothed);
+            let up = up_smoothed as u64;
+            let down = down_smoothed as u64;
+
+            components.refresh(false);
+            let cpu_temp = cpu_temperature(&components);
+
+            let reading = TrayReading {
+                cpu,
+                cpu_history: cpu_history.make_contiguous(),
+                cpu_temp,
+                up,
+                down,
+                memory_percent,
+                over_data_cap,
+            };
+            // Paused: keep sampling for data usage and history, leave the tray as is
+            if !app.state::<AppState>().monitoring_paused.load(Ordering::Relaxed) {
+                crate::modules::tray::update_status_bar(&app, status_bar.as_ref(), mode, &reading, &settings.tray, &settings.format);
+                crate::modules::tray::update_stats_menu(&app, &reading, &settings.format);
+
+                if settings.tray.health_icon {
+                    let health = crate::modules::tray::system_health(&reading, &settings.tray);
+                    if last_health != Some(health) {
+                        crate::modules::tray::update_tray_icon(&app, health);
+                        last_health = Some(health);
+                    }
+                } else if last_health.take().is_some() {
+                    crate::modules::tray::reset_tray_icon(&app);
+                }
+            }
+
+            record_stats_sample(&app.state::<AppState>(), StatsSample {
+                timestamp: chrono::Utc::now().timestamp_millis(),
+                cpu_usage: cpu,
+                memory_used,
+                memory_total,
+                network_speed_up: up,
+                network_speed_down: down,
+            });
+
+            // Emit so the window needn't poll; keep a copy for the metrics endpoint
+            let emit = settings.emit_stats && main_window_visible(&app);
+            if emit || settings.metrics {
+                let stats = tray_stats(cpu, memory_used, memory_total, up, down, &mut disks, &settings.disk_mount);
+                if settings.metrics {
+                    *app.state::<AppState>().latest_stats.lock_or_recover() = Some(stats.clone());
+                }
+                if emit {
+                    let _ = app.emit("system-stats", stats);
+                }
+            }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `vm_stat` output captured on an Apple Silicon Mac
+    const VM_STAT_SAMPLE: &str = "\
+Mach Virtual Memory Statistics: (page size of 16384 bytes)
+Pages free:                                3791.
+Pages active:                            190204.
+Pages inactive:                          187424.
+Pages speculative:                         1976.
+Pages throttled:                              0.
+Pages wired down:                        124321.
+Pages purgeable:                           2541.
+\"Translation faults\":                 931012345.
+Pages copy-on-write:                   30004432.
+Pages zero filled:                    412341234.
+Pages reactivated:                      1234567.
+Pages purged:                            345678.
+File-backed pages:                       160123.
+Anonymous pages:                         219481.
+Pages stored in compressor:              512345.
+Pages occupied by compressor:            143210.
+Decompressions:                          123456.
+Compressions:                            234567.
+Pageins:                                 345678.
+Pageouts:                                  4567.
+Swapins:                                      0.
+Swapouts:                                     0.
+";
+
+    #[test]
+    fn parse_vm_stat_sample() {
+        let stat = parse_vm_stat(VM_STAT_SAMPLE);
+        assert_eq!(stat.free, 3791 * 16384);
+        assert_eq!(stat.wired, 124321 * 16384);
+        assert_eq!(stat.compressed, 143210 * 16384);
+        assert_eq!(stat.file_backed, 160123 * 16384);
+        assert_eq!(stat.anonymous, 219481 * 16384);
+        assert_eq!(stat.purgeable, 2541 * 16384);
+        assert_eq!(stat.app_memory(), (219481 - 2541) * 16384);
+    }
+
+    #[test]
+    fn parse_vm_stat_reads_page_size_header() {
+        let stat = parse_vm_stat("Mach Virtual Memory Statistics: (page size of 4096 bytes)\nPages free: 10.\n");
+        assert_eq!(stat.free, 10 * 4096);
+    }
+}
@@ -1,78 +1,504 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
 use tauri::{AppHandle, Manager};
-use objc2::{rc::Allocated, MainThreadMarker, ClassType};
+use tauri::image::Image;
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "macos")]
+use objc2::{define_class, msg_send, sel, rc::{Allocated, Retained}, ClassType, DefinedClass, MainThreadMarker, MainThreadOnly};
+#[cfg(target_os = "macos")]
+use objc2_app_kit::{NSColor, NSFont, NSFontWeightRegular, NSStatusBar, NSStatusItem, NSVariableStatusItemLength};
+#[cfg(target_os = "macos")]
 use objc2_foundation::{
-    ns_string, NSDictionary, NSMutableAttributedString, NSString, NSRange,
+    ns_string, NSMutableAttributedString, NSObject, NSString, NSRange,
 };
+#[cfg(target_os = "macos")]
 use objc2::runtime::AnyObject;
-use crate::modules::AppState;
+#[cfg(target_os = "macos")]
+use crate::modules::ThreadSafeStatusItem;
+use crate::modules::{AppState, TrayLabel};
+use crate::modules::config::{get_config_dir, DisplayMode, TrayConfig};
+#[cfg(target_os = "macos")]
+use crate::modules::config::load_config;
+use crate::modules::utils::{LockExt, CPU_CRITICAL_PERCENT, CPU_WARN_PERCENT};
+use crate::modules::history::last_upload_url;
+use crate::modules::system::request_tray_refresh;
 
 // Re-export utilities for use in other modules
-pub use crate::modules::utils::{format_speed, get_cpu_color, get_network_color};
+pub use crate::modules::utils::{cpu_icon, cpu_over_temperature, format_speed, sparkline, FormatOptions};
+#[cfg(target_os = "macos")]
+use crate::modules::utils::{cpu_segment_color, get_memory_color, get_network_color};
 
-pub fn update_status_bar(app: &AppHandle, cpu: f32, up: u64, down: u64) {
-    let cpu_str = format!("{:.0}%", cpu);
-    let up_str = format!("{}", format_speed(up));
-    let down_str = format!("{}", format_speed(down));
-
-    let sep1 = ",";
-    let sep2 = ",";
+/// One sample from the tray loop
+pub struct TrayReading<'a> {
+    pub cpu: f32,
+    pub cpu_history: &'a [f32],
+    /// CPU die temperature in °C, when the sensors expose one
+    pub cpu_temp: Option<f32>,
+    pub up: u64,
+    pub down: u64,
+    pub memory_percent: f32,
+    /// Today's network usage is over `general.daily_data_cap_gb`
+    pub over_data_cap: bool,
+}
 
-    let cpu_len = cpu_str.encode_utf16().count();
-    let sep1_len = sep1.encode_utf16().count();
-    let up_len = up_str.encode_utf16().count();
-    let sep2_len = sep2.encode_utf16().count();
-    let down_len = down_str.encode_utf16().count();
+/// Color rule for a segment; NSColor isn't Send, so it's resolved on the main thread
+#[derive(Clone, Copy)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub enum SegmentColor {
+    Plain,
+    /// Usage and temperature
+    Cpu(f32, Option<f32>),
+    Network(u64),
+    /// Network text once the daily data cap is used up (`tray.network.tint_over_cap`)
+    DataCap,
+    Memory(f32),
+}
 
-    let full_text = format!("{}{}{}{}{}", cpu_str, sep1, up_str, sep2, down_str);
+/// A run of menu-bar text sharing one color
+pub struct Segment {
+    pub text: String,
+    // Only the native status item renders colors
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub color: SegmentColor,
+}
 
-    let handle = app.clone();
+impl Segment {
+    fn plain(text: impl Into<String>) -> Self {
+        Self { text: text.into(), color: SegmentColor::Plain }
+    }
 
-    let _ = app.run_on_main_thread(move || {
-        let mtm = unsafe { MainThreadMarker::new_unchecked() };
+    fn colored(text: impl Into<String>, color: SegmentColor) -> Self {
+        Self { text: text.into(), color }
+    }
+}
 
-        let state = handle.state::<AppState>();
-        let lock = state.status_item.lock().unwrap();
+/// Template rendered for a display mode; `all` uses the configurable `tray.format`
+fn mode_template(mode: DisplayMode, tray: &TrayConfig) -> &str {
+    match mode {
+        DisplayMode::All => tray.format_template(),
+        DisplayMode::Cpu => "{spark} {cpu}",
+        DisplayMode::Network => "{up},{down}",
+        DisplayMode::Memory => "MEM {mem}",
+    }
+}
 
-        if let Some(wrapper) = lock.as_ref() {
-            let item = &wrapper.0;
+fn network_color(bytes_per_sec: u64, reading: &TrayReading, tray: &TrayConfig) -> SegmentColor {
+    if tray.network.tint_over_cap && reading.over_data_cap {
+        SegmentColor::DataCap
+    } else {
+        SegmentColor::Network(bytes_per_sec)
+    }
+}
 
-            let full_ns = NSString::from_str(&full_text);
+fn render_token(token: &str, reading: &TrayReading, tray: &TrayConfig, format: &FormatOptions) -> Option<Segment> {
+    let cpu_color = SegmentColor::Cpu(reading.cpu, reading.cpu_temp);
+    let segment = match token {
+        // Emoji carry their own color, so only the fallback glyph is tinted
+        "icon" if tray.emoji_icon && cpu_over_temperature(reading.cpu_temp, tray.cpu_temp_critical) => Segment::plain("🔴"),
+        "icon" if tray.emoji_icon => Segment::plain(cpu_icon(reading.cpu)),
+        "icon" => Segment::colored("●", cpu_color),
+        "spark" => Segment::colored(sparkline(reading.cpu_history, 100.0), cpu_color),
+        "cpu" => Segment::colored(format!("{:.0}%", reading.cpu), cpu_color),
+        "up" => Segment::colored(format_speed(reading.up, format), network_color(reading.up, reading, tray)),
+        "down" => Segment::colored(format_speed(reading.down, format), network_color(reading.down, reading, tray)),
+        "mem" => Segment::colored(format!("{:.0}%", reading.memory_percent), SegmentColor::Memory(reading.memory_percent)),
+        _ => return None,
+    };
+    Some(segment)
+}
 
-            let alloc_mut: Allocated<NSMutableAttributedString> = unsafe {
-                objc2::msg_send![NSMutableAttributedString::class(), alloc]
-            };
-            let mut_attr_str = NSMutableAttributedString::initWithString(alloc_mut, &full_ns);
+/// Split a template into literal text and `{token}` segments; unknown tokens stay literal
+fn build_segments(template: &str, reading: &TrayReading, label: &TrayLabel, tray: &TrayConfig, format: &FormatOptions) -> Vec<Segment> {
+    let mut segments = vec![Segment::plain(label.prefix.clone())];
 
-            // Apply CPU color
-            let cpu_range = NSRange::new(0, cpu_len);
-            let cpu_key = ns_string!("NSColor");
-            let cpu_dict = NSDictionary::from_slices(&[cpu_key], &[&*get_cpu_color(cpu)]);
-            let cpu_dict_ptr: &NSDictionary<NSString, AnyObject> = unsafe { std::mem::transmute(&*cpu_dict) };
-            unsafe {
-                mut_attr_str.setAttributes_range(Some(cpu_dict_ptr), cpu_range);
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|i| open + i) else {
+            break;
+        };
+        match render_token(&rest[open + 1..close], reading, tray, format) {
+            Some(segment) => {
+                segments.push(Segment::plain(&rest[..open]));
+                segments.push(segment);
             }
+            None => segments.push(Segment::plain(&rest[..=close])),
+        }
+        rest = &rest[close + 1..];
+    }
+    segments.push(Segment::plain(rest));
 
-            // Apply upload color
-            let up_start = cpu_len + sep1_len;
-            let up_range = NSRange::new(up_start, up_len);
-            let up_dict = NSDictionary::from_slices(&[cpu_key], &[&*get_network_color(up)]);
-            let up_dict_ptr: &NSDictionary<NSString, AnyObject> = unsafe { std::mem::transmute(&*up_dict) };
-            unsafe {
-                mut_attr_str.setAttributes_range(Some(up_dict_ptr), up_range);
-            }
+    segments.push(Segment::plain(label.suffix.clone()));
+    segments
+}
 
-            // Apply download color
-            let down_start = up_start + up_len + sep2_len;
-            let down_range = NSRange::new(down_start, down_len);
-            let down_dict = NSDictionary::from_slices(&[cpu_key], &[&*get_network_color(down)]);
-            let down_dict_ptr: &NSDictionary<NSString, AnyObject> = unsafe { std::mem::transmute(&*down_dict) };
-            unsafe {
-                mut_attr_str.setAttributes_range(Some(down_dict_ptr), down_range);
-            }
+/// Where the tray loop draws the menu-bar text. macOS uses a native status
+/// item with colored segments; elsewhere the Tauri tray title shows plain text.
+pub trait StatusBar: Send {
+    fn render(&self, app: &AppHandle, segments: Vec<Segment>, tray: &TrayConfig);
+}
+
+/// Status bar for the current platform
+#[cfg(target_os = "macos")]
+pub fn platform_status_bar() -> Box<dyn StatusBar> {
+    Box::new(NativeStatusBar)
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn platform_status_bar() -> Box<dyn StatusBar> {
+    Box::new(TrayTitleStatusBar)
+}
+
+pub fn update_status_bar(app: &AppHandle, status_bar: &dyn StatusBar, mode: DisplayMode, reading: &TrayReading, tray: &TrayConfig, format: &FormatOptions) {
+    let label = app.state::<AppState>().tray_label.lock_or_recover().clone();
+    let segments = build_segments(mode_template(mode, tray), reading, &label, tray, format);
+    status_bar.render(app, segments, tray);
+}
+
+/// Without the native status item, show the text uncolored as the Tauri tray title
+#[cfg(not(target_os = "macos"))]
+pub struct TrayTitleStatusBar;
+
+#[cfg(not(target_os = "macos"))]
+impl StatusBar for TrayTitleStatusBar {
+    fn render(&self, app: &AppHandle, segments: Vec<Segment>, _tray: &TrayConfig) {
+        let text: String = segments.iter().map(|segment| segment.text.as_str()).collect();
+        if let Some(tray) = app.tray_by_id(TRAY_ICON_ID) {
+            let _ = tray.set_title(Some(text));
+        }
+    }
+}
+
+/// The NSStatusItem created by `setup_status_item`, with an attributed title
+#[cfg(target_os = "macos")]
+pub struct NativeStatusBar;
+
+#[cfg(target_os = "macos")]
+impl StatusBar for NativeStatusBar {
+    fn render(&self, app: &AppHandle, segments: Vec<Segment>, tray: &TrayConfig) {
+        let warn_mbps = tray.network.warn_mbps;
+        let critical_mbps = tray.network.critical_mbps;
+        let memory_colors = tray.memory.clone();
+        let cpu_temp_critical = tray.cpu_temp_critical;
+        let font_size = tray.font_size;
+
+        let handle = app.clone();
+
+        let _ = app.run_on_main_thread(move || {
+            let mtm = unsafe { MainThreadMarker::new_unchecked() };
+
+            let state = handle.state::<AppState>();
+            let lock = state.status_item.lock_or_recover();
+
+            if let Some(wrapper) = lock.as_ref() {
+                let item = &wrapper.0;
+
+                let full_text: String = segments.iter().map(|segment| segment.text.as_str()).collect();
+                let full_ns = NSString::from_str(&full_text);
+
+                let alloc_mut: Allocated<NSMutableAttributedString> = unsafe {
+                    objc2::msg_send![NSMutableAttributedString::class(), alloc]
+                };
+                let mut_attr_str = NSMutableAttributedString::initWithString(alloc_mut, &full_ns);
+
+                // Monospaced digits keep the item's width stable as the numbers change
+                let font_size = if font_size > 0.0 { font_size } else { NSFont::systemFontSize() };
+                let font = NSFont::monospacedDigitSystemFontOfSize_weight(font_size, unsafe { NSFontWeightRegular });
+                unsafe {
+                    mut_attr_str.addAttribute_value_range(ns_string!("NSFont"), &font, NSRange::new(0, full_ns.length()));
+                }
+
+                let color_key = ns_string!("NSColor");
+                // UTF-16 offsets, so emoji surrogate pairs (label, {icon}) keep ranges aligned
+                let mut start = 0;
+                for segment in &segments {
+                    let len = segment.text.encode_utf16().count();
+                    let color = match segment.color {
+                        SegmentColor::Plain => None,
+                        SegmentColor::Cpu(cpu, temp) => Some(cpu_segment_color(cpu, temp, cpu_temp_critical)),
+                        SegmentColor::Network(bytes) => Some(get_network_color(bytes, warn_mbps, critical_mbps)),
+                        SegmentColor::DataCap => Some(NSColor::redColor()),
+                        SegmentColor::Memory(percent) => Some(get_memory_color(percent, memory_colors.warn_percent, memory_colors.critical_percent)),
+                    };
+                    if let (Some(color), true) = (color, len > 0) {
+                        // Add rather than set, so the font attribute is kept
+                        unsafe {
+                            mut_attr_str.addAttribute_value_range(color_key, &color, NSRange::new(start, len));
+                        }
+                    }
+                    start += len;
+                }
 
-            if let Some(button) = item.button(mtm) {
-                button.setAttributedTitle(&mut_attr_str);
+                if let Some(button) = item.button(mtm) {
+                    button.setAttributedTitle(&mut_attr_str);
+                }
             }
+        });
+    }
+}
+
+/// Refresh the disabled stats items in the tray menu
+pub fn update_stats_menu(app: &AppHandle, reading: &TrayReading, format: &FormatOptions) {
+    let cpu_text = match reading.cpu_temp {
+        Some(temp) => format!("CPU: {:.0}%  {:.0}°C", reading.cpu, temp),
+        None => format!("CPU: {:.0}%", reading.cpu),
+    };
+    let memory_text = format!("Memory: {:.0}%", reading.memory_percent);
+    let network_text = format!(
+        "Network: ↑ {}  ↓ {}",
+        format_speed(reading.up, format).trim(),
+        format_speed(reading.down, format).trim()
+    );
+
+    let handle = app.clone();
+    let _ = app.run_on_main_thread(move || {
+        let state = handle.state::<AppState>();
+        let lock = state.stats_items.lock_or_recover();
+        if let Some(items) = lock.as_ref() {
+            let _ = items.cpu.set_text(cpu_text);
+            let _ = items.memory.set_text(memory_text);
+            let _ = items.network.set_text(network_text);
         }
     });
 }
+
+/// Tray icon id, for looking the icon up from the tray loop
+pub const TRAY_ICON_ID: &str = "main";
+
+/// Base tray icon, embedded at build time
+const TRAY_ICON_BYTES: &[u8] = include_bytes!("../../icons/tray-icon-rounded.png");
+
+/// Overall system state shown by the tray icon's status dot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Health {
+    Good,
+    Warn,
+    Critical,
+}
+
+/// Worst of CPU usage, memory pressure and CPU temperature
+pub fn system_health(reading: &TrayReading, tray: &TrayConfig) -> Health {
+    let cpu = if reading.cpu >= CPU_CRITICAL_PERCENT {
+        Health::Critical
+    } else if reading.cpu >= CPU_WARN_PERCENT {
+        Health::Warn
+    } else {
+        Health::Good
+    };
+    let memory = if reading.memory_percent >= tray.memory.critical_percent {
+        Health::Critical
+    } else if reading.memory_percent >= tray.memory.warn_percent {
+        Health::Warn
+    } else {
+        Health::Good
+    };
+    let temperature = if cpu_over_temperature(reading.cpu_temp, tray.cpu_temp_critical) {
+        Health::Critical
+    } else {
+        Health::Good
+    };
+    cpu.max(memory).max(temperature)
+}
+
+// HEALTH_ICONS: PNGs of the tray icon with a green/yellow/red dot, drawn by
+// build.rs so nothing is rendered at runtime
+include!(concat!(env!("OUT_DIR"), "/health_icons.rs"));
+
+/// Put back the plain icon, e.g. after `tray.health_icon` is turned off
+pub fn reset_tray_icon(app: &AppHandle) {
+    if let (Some(tray), Ok(icon)) = (app.tray_by_id(TRAY_ICON_ID), Image::from_bytes(TRAY_ICON_BYTES)) {
+        let _ = tray.set_icon(Some(icon));
+    }
+}
+
+/// Swap the tray icon for the given health. The tray loop only calls this
+/// when the level changes, not on every tick.
+pub fn update_tray_icon(app: &AppHandle, health: Health) {
+    let icon = match Image::from_bytes(HEALTH_ICONS[health as usize]) {
+        Ok(icon) => icon,
+        Err(e) => {
+            log::warn!("Failed to load {:?} tray icon: {}", health, e);
+            return;
+        }
+    };
+    if let Some(tray) = app.tray_by_id(TRAY_ICON_ID) {
+        if let Err(e) = tray.set_icon(Some(icon)) {
+            log::warn!("Failed to update tray icon: {}", e);
+        }
+    }
+}
+
+/// Enable "Copy Last URL" only when there is an upload to copy
+pub fn update_copy_url_item(app: &AppHandle) {
+    let enabled = last_upload_url().is_some();
+    let handle = app.clone();
+    let _ = app.run_on_main_thread(move || {
+        if let Some(item) = handle.state::<AppState>().copy_url_item.lock_or_recover().as_ref() {
+            let _ = item.set_enabled(enabled);
+        }
+    });
+}
+
+/// Display mode picked by clicking the menu-bar text, kept out of config.toml
+/// so a click doesn't rewrite the user's file or trigger a config reload
+#[derive(Debug, Serialize, Deserialize)]
+struct TrayState {
+    display_mode: DisplayMode,
+    /// `tray.display_mode` when the mode was picked; editing it in config.toml
+    /// takes over from the clicked mode
+    config_display_mode: DisplayMode,
+}
+
+/// Tray state file: stored next to config.toml
+fn get_tray_state_path() -> PathBuf {
+    get_config_dir().join("tray_state.json")
+}
+
+/// Mode to show: the last clicked one, unless `tray.display_mode` has been
+/// changed since
+pub fn effective_display_mode(config_mode: DisplayMode) -> DisplayMode {
+    fs::read_to_string(get_tray_state_path())
+        .ok()
+        .and_then(|contents| serde_json::from_str::<TrayState>(&contents).ok())
+        .filter(|state| state.config_display_mode == config_mode)
+        .map_or(config_mode, |state| state.display_mode)
+}
+
+#[cfg(target_os = "macos")]
+fn save_display_mode(mode: DisplayMode) {
+    let state = TrayState { display_mode: mode, config_display_mode: load_config().tray.display_mode };
+    let path = get_tray_state_path();
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    match serde_json::to_string(&state) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                log::error!("Failed to save tray state: {}", e);
+            }
+        }
+        Err(e) => log::error!("Failed to encode tray state: {}", e),
+    }
+}
+
+/// Advance the display mode, remember it and redraw right away
+#[cfg(target_os = "macos")]
+pub fn cycle_display_mode(app: &AppHandle) {
+    let mode = {
+        let state = app.state::<AppState>();
+        let mut mode = state.display_mode.lock_or_recover();
+        *mode = mode.next();
+        *mode
+    };
+    log::info!("Tray display mode: {:?}", mode);
+
+    save_display_mode(mode);
+    request_tray_refresh();
+}
+
+#[cfg(target_os = "macos")]
+define_class!(
+    // SAFETY: NSObject has no subclassing requirements and this class has no Drop impl
+    #[unsafe(super(NSObject))]
+    #[thread_kind = MainThreadOnly]
+    #[name = "PulseStatusItemTarget"]
+    #[ivars = AppHandle]
+    struct StatusItemTarget;
+
+    impl StatusItemTarget {
+        #[unsafe(method(statusItemClicked:))]
+        fn status_item_clicked(&self, _sender: Option<&AnyObject>) {
+            cycle_display_mode(self.ivars());
+        }
+    }
+);
+
+#[cfg(target_os = "macos")]
+impl StatusItemTarget {
+    fn new(mtm: MainThreadMarker, app: AppHandle) -> Retained<Self> {
+        let this = Self::alloc(mtm).set_ivars(app);
+        unsafe { msg_send![super(this), init] }
+    }
+}
+
+/// Cycle the display mode when the menu-bar text is clicked
+#[cfg(target_os = "macos")]
+fn attach_click_handler(item: &NSStatusItem, app: AppHandle, mtm: MainThreadMarker) {
+    let Some(button) = item.button(mtm) else {
+        return;
+    };
+    let target = StatusItemTarget::new(mtm, app);
+    let target_obj: &AnyObject = &target;
+    unsafe {
+        button.setTarget(Some(target_obj));
+        button.setAction(Some(sel!(statusItemClicked:)));
+    }
+    // NSControl holds its target weakly; the status item lives as long as the app
+    std::mem::forget(target);
+}
+
+/// Create the native menu-bar text item. Any failure is logged and the app
+/// carries on with only the Tauri tray icon.
+#[cfg(target_os = "macos")]
+pub fn setup_status_item(app: &AppHandle) {
+    let Some(mtm) = MainThreadMarker::new() else {
+        log::error!("Status item setup must run on the main thread; menu-bar stats disabled");
+        return;
+    };
+    let status_bar = NSStatusBar::systemStatusBar();
+    let status_item = status_bar.statusItemWithLength(NSVariableStatusItemLength);
+
+    let Some(button) = status_item.button(mtm) else {
+        log::error!("Status item has no button; menu-bar stats disabled");
+        status_bar.removeStatusItem(&status_item);
+        return;
+    };
+    button.setTitle(ns_string!("System Monitor"));
+
+    attach_click_handler(&status_item, app.clone(), mtm);
+    *app.state::<AppState>().status_item.lock_or_recover() = Some(ThreadSafeStatusItem(status_item));
+}
+
+/// No native status item; TrayTitleStatusBar uses the tray title instead
+#[cfg(not(target_os = "macos"))]
+pub fn setup_status_item(_app: &AppHandle) {
+    log::info!("Native status item is macOS-only; showing stats in the tray title");
+}
+
+/// Set the uncolored menu-bar prefix/suffix; `None` leaves the current value
+#[tauri::command]
+pub fn set_tray_label(state: tauri::State<AppState>, prefix: Option<String>, suffix: Option<String>) -> TrayLabel {
+    let mut label = state.tray_label.lock_or_recover();
+    if let Some(prefix) = prefix {
+        label.prefix = prefix;
+    }
+    if let Some(suffix) = suffix {
+        label.suffix = suffix;
+    }
+    label.clone()
+}
+
+/// Freeze or resume the menu bar; resuming refreshes it right away
+#[tauri::command]
+pub fn set_monitoring_paused(app: AppHandle, paused: bool) {
+    let state = app.state::<AppState>();
+    state.monitoring_paused.store(paused, Ordering::Relaxed);
+    if let Some(item) = state.pause_item.lock_or_recover().as_ref() {
+        let _ = item.set_checked(paused);
+    }
+    if !paused {
+        request_tray_refresh();
+    }
+}
+
+/// Tray menu handler for "Pause Monitoring"
+pub fn toggle_monitoring_paused(app: &AppHandle) {
+    let paused = app.state::<AppState>().monitoring_paused.load(Ordering::Relaxed);
+    set_monitoring_paused(app.clone(), !paused);
+}
+
+/// Get the current menu-bar prefix/suffix
+#[tauri::command]
+pub fn get_tray_label(state: tauri::State<AppState>) -> TrayLabel {
+    state.tray_label.lock_or_recover().clone()
+}
@@ -1,32 +1,85 @@
-use std::time::Duration;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::thread;
 use arboard::Clipboard;
 use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
 use image::{ImageBuffer, RgbaImage};
-use tauri::{Emitter, Manager};
-use crate::modules::UploadResult;
-use crate::modules::config::load_config;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, RETRY_AFTER};
+use sha2::{Digest, Sha256};
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+use crate::modules::{AppState, IndexedUploadResult, Rect, UploadResult, UploadTestResult, UploadResultMinimal, UploadBackendInfo, ClipboardBenchmark};
+use crate::modules::config::{load_config, Config, ResultVerbosity, UploadFormat, UploadMethod};
+use crate::modules::history::{find_upload_by_hash, last_upload_url, record_upload};
+use crate::modules::tray::update_copy_url_item;
+use crate::modules::window_state::show_main_window;
+use crate::modules::utils::{backoff_delay, format_size, format_speed, json_path, retry_after, FormatOptions, LockExt};
+use crate::modules::error::PulseError;
 
-/// Get image from clipboard as base64 data URL
+/// Raw GIF bytes from the pasteboard, if it holds a GIF. arboard only
+/// exposes images as a single RGBA frame.
+#[cfg(target_os = "macos")]
+fn clipboard_gif() -> Option<Vec<u8>> {
+    use objc2_app_kit::NSPasteboard;
+    use objc2_foundation::ns_string;
+
+    let pasteboard = NSPasteboard::generalPasteboard();
+    let data = pasteboard.dataForType(ns_string!("com.compuserve.gif"))?;
+    let bytes = data.to_vec();
+    bytes.starts_with(b"GIF8").then_some(bytes)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn clipboard_gif() -> Option<Vec<u8>> {
+    None
+}
+
+/// Get image from clipboard as base64 data URL. GIFs are passed through
+/// unchanged (`format: "gif"`) so animation survives.
 #[tauri::command]
 pub fn get_clipboard_image() -> crate::modules::ClipboardImage {
+    if let Some(gif) = clipboard_gif() {
+        return crate::modules::ClipboardImage {
+            has_image: true,
+            data_url: Some(format!("data:image/gif;base64,{}", base64::engine::general_purpose::STANDARD.encode(&gif))),
+            size_bytes: Some(gif.len()),
+            format: Some("gif".to_string()),
+            error: None,
+        };
+    }
+
     match Clipboard::new() {
         Ok(mut clipboard) => {
             match clipboard.get_image() {
-                Ok(image_data) => {
-                    let size = image_data.bytes.len();
-                    let data_url = format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(&image_data.bytes));
-                    crate::modules::ClipboardImage {
+                Ok(image_data) => match encode_image(&image_data.bytes, image_data.width, image_data.height, UploadFormat::Png, 100, None) {
+                    // arboard hands back raw RGBA; the data URL has to carry a real PNG
+                    Ok(png) => crate::modules::ClipboardImage {
                         has_image: true,
-                        data_url: Some(data_url),
-                        size_bytes: Some(size),
+                        data_url: Some(format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(&png))),
+                        size_bytes: Some(png.len()),
+                        format: Some("png".to_string()),
                         error: None,
-                    }
-                }
+                    },
+                    Err(e) => crate::modules::ClipboardImage {
+                        has_image: false,
+                        data_url: None,
+                        size_bytes: None,
+                        format: None,
+                        error: Some(e.to_string()),
+                    },
+                },
                 Err(_) => crate::modules::ClipboardImage {
                     has_image: false,
                     data_url: None,
                     size_bytes: None,
+                    format: None,
                     error: Some("No image in clipboard".to_string()),
                 }
             }
@@ -35,55 +88,250 @@ pub fn get_clipboard_image() -> crate::modules::ClipboardImage {
             has_image: false,
             data_url: None,
             size_bytes: None,
+            format: None,
             error: Some(format!("Failed to access clipboard: {}", e)),
         }
     }
 }
 
-/// Convert raw RGBA bytes from clipboard to PNG format
-pub fn rgba_to_png(rgba_data: &[u8], width: usize, height: usize) -> Result<Vec<u8>, String> {
+const CLIPBOARD_BENCHMARK_ITERATIONS: u32 = 5;
+
+/// Measure clipboard access (and image read, if present) latency in milliseconds
+#[tauri::command]
+pub fn benchmark_clipboard() -> Result<ClipboardBenchmark, PulseError> {
+    // Probe once outside the timed loop so the no-image case only times access
+    let has_image = Clipboard::new()
+        .map_err(|e| PulseError::Clipboard(format!("Failed to access clipboard: {}", e)))?
+        .get_image()
+        .is_ok();
+
+    let mut timings = Vec::with_capacity(CLIPBOARD_BENCHMARK_ITERATIONS as usize);
+    for _ in 0..CLIPBOARD_BENCHMARK_ITERATIONS {
+        let start = Instant::now();
+        let mut clipboard = Clipboard::new().map_err(|e| PulseError::Clipboard(format!("Failed to access clipboard: {}", e)))?;
+        if has_image {
+            clipboard.get_image().map_err(|e| PulseError::Clipboard(format!("Failed to read clipboard image: {}", e)))?;
+        }
+        timings.push(start.elapsed().as_secs_f64() * 1000.0);
+    }
+
+    let min_ms = timings.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max_ms = timings.iter().cloned().fold(0.0, f64::max);
+    let avg_ms = timings.iter().sum::<f64>() / timings.len() as f64;
+
+    log::info!("Clipboard benchmark (image: {}): min {:.1}ms, avg {:.1}ms, max {:.1}ms", has_image, min_ms, avg_ms, max_ms);
+
+    Ok(ClipboardBenchmark {
+        iterations: CLIPBOARD_BENCHMARK_ITERATIONS,
+        has_image,
+        min_ms,
+        avg_ms,
+        max_ms,
+    })
+}
+
+/// Get text from clipboard; `None` when it holds no text (e.g. only an image)
+#[tauri::command]
+pub fn get_clipboard_text() -> Result<Option<String>, PulseError> {
+    let mut clipboard = Clipboard::new().map_err(|e| PulseError::Clipboard(format!("Failed to access clipboard: {}", e)))?;
+    match clipboard.get_text() {
+        Ok(text) => Ok(Some(text)),
+        Err(arboard::Error::ContentNotAvailable) => Ok(None),
+        Err(e) => Err(PulseError::Clipboard(format!("Failed to read clipboard text: {}", e))),
+    }
+}
+
+/// Encode raw RGBA bytes from clipboard in the configured format.
+/// `quality` (1-100) applies to JPEG only; WebP is encoded losslessly.
+/// `optimize_level` (oxipng preset 0-6) runs an extra PNG compression pass.
+pub fn encode_image(rgba_data: &[u8], width: usize, height: usize, format: UploadFormat, quality: u8, optimize_level: Option<u8>) -> Result<Vec<u8>, PulseError> {
     let img: RgbaImage = ImageBuffer::from_raw(
         width as u32,
         height as u32,
-        rgba_data.to_vec(),
-    ).ok_or("Failed to create image buffer")?;
+        unpadded_rgba(rgba_data, width, height)?,
+    ).ok_or_else(|| PulseError::Encode("Failed to create image buffer".to_string()))?;
 
-    let mut png_bytes = Vec::new();
-    img.write_to(&mut std::io::Cursor::new(&mut png_bytes), image::ImageFormat::Png)
-        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    let mut encoded = Vec::new();
+    let mut cursor = std::io::Cursor::new(&mut encoded);
+    match format {
+        UploadFormat::Png => img.write_to(&mut cursor, image::ImageFormat::Png),
+        UploadFormat::Jpeg => {
+            // JPEG has no alpha channel
+            let rgb = image::DynamicImage::ImageRgba8(img).to_rgb8();
+            JpegEncoder::new_with_quality(&mut cursor, quality.clamp(1, 100)).encode_image(&rgb)
+        }
+        UploadFormat::Webp => img.write_to(&mut cursor, image::ImageFormat::WebP),
+    }
+    .map_err(|e| PulseError::Encode(format!("Failed to encode {}: {}", format.extension().to_uppercase(), e)))?;
 
-    Ok(png_bytes)
+    match (format, optimize_level) {
+        (UploadFormat::Png, Some(level)) => Ok(optimize_png(encoded, level)),
+        _ => Ok(encoded),
+    }
 }
 
-/// Upload image data to server with retry logic
-#[tauri::command]
-pub fn upload_image(image_base64: String, retry_count: Option<u32>) -> Result<UploadResult, String> {
-    upload_image_with_retry(image_base64, retry_count.unwrap_or(0))
-}
+/// Tightly packed RGBA for `width` x `height`. Some captures (e.g. Retina)
+/// pad each row, so a length that divides evenly into wider rows is cropped
+/// row by row; any other mismatch is an error rather than a corrupt image.
+fn unpadded_rgba(rgba_data: &[u8], width: usize, height: usize) -> Result<Vec<u8>, PulseError> {
+    let row_bytes = width
+        .checked_mul(4)
+        .filter(|_| height > 0)
+        .ok_or_else(|| PulseError::Encode(format!("Invalid image dimensions {}x{}", width, height)))?;
+    let expected = row_bytes
+        .checked_mul(height)
+        .ok_or_else(|| PulseError::Encode(format!("Invalid image dimensions {}x{}", width, height)))?;
 
-fn upload_image_with_retry(image_base64: String, retry_count: u32) -> Result<UploadResult, String> {
-    let config = load_config();
+    if rgba_data.len() == expected {
+        return Ok(rgba_data.to_vec());
+    }
 
-    if config.upload.url.is_empty() || config.upload.token.is_empty() {
-        return Err("Upload not configured. Please edit ~/.config/pulse/config.toml".to_string());
+    let stride = rgba_data.len() / height;
+    if rgba_data.len() % height == 0 && stride > row_bytes {
+        log::warn!("Image rows are padded: stride {} bytes for {} bytes of pixels, cropping", stride, row_bytes);
+        return Ok(rgba_data.chunks_exact(stride).flat_map(|row| &row[..row_bytes]).copied().collect());
     }
 
-    let url = &config.upload.url;
+    Err(PulseError::Encode(format!(
+        "Image data is {} bytes, expected {} for {}x{} RGBA",
+        rgba_data.len(), expected, width, height
+    )))
+}
+
+/// Losslessly recompress a PNG with oxipng; falls back to the input on failure
+fn optimize_png(png: Vec<u8>, level: u8) -> Vec<u8> {
+    let started = Instant::now();
+    let options = oxipng::Options::from_preset(level.min(6));
+    match oxipng::optimize_from_memory(&png, &options) {
+        Ok(optimized) => {
+            log::info!("oxipng level {}: {} -> {} bytes in {:?}", level, png.len(), optimized.len(), started.elapsed());
+            optimized
+        }
+        Err(e) => {
+            log::warn!("oxipng failed, uploading unoptimized PNG: {}", e);
+            png
+        }
+    }
+}
+
+const HEIC_BRANDS: [&[u8; 4]; 8] = [b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1"];
+
+/// Detect HEIC/HEIF by its ISO BMFF `ftyp` box and major brand
+fn is_heic(bytes: &[u8]) -> bool {
+    bytes.len() >= 12 && &bytes[4..8] == b"ftyp" && HEIC_BRANDS.iter().any(|brand| &bytes[8..12] == *brand)
+}
 
-    let base64_data = if image_base64.starts_with("data:image/") {
-        image_base64.split(',').nth(1).unwrap_or(&image_base64)
+/// Mime type and extension for PNG/JPEG/GIF/WebP, detected from magic bytes
+fn detect_image_type(bytes: &[u8]) -> Option<(&'static str, &'static str)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(("image/png", "png"))
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some(("image/jpeg", "jpg"))
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some(("image/gif", "gif"))
+    } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some(("image/webp", "webp"))
     } else {
-        &image_base64
+        None
+    }
+}
+
+/// Convert HEIC bytes to PNG using macOS `sips`
+pub fn heic_to_png(heic_bytes: &[u8]) -> Result<Vec<u8>, PulseError> {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let tmp_dir = std::env::temp_dir();
+    let input = tmp_dir.join(format!("pulse-{}-{}.heic", std::process::id(), stamp));
+    let output = tmp_dir.join(format!("pulse-{}-{}.png", std::process::id(), stamp));
+
+    std::fs::write(&input, heic_bytes).map_err(|e| PulseError::Io(format!("Failed to write HEIC temp file: {}", e)))?;
+
+    let result = std::process::Command::new("sips")
+        .args(["-s", "format", "png"])
+        .arg(&input)
+        .arg("--out")
+        .arg(&output)
+        .output()
+        .map_err(|e| PulseError::Encode(format!("Failed to execute sips: {}", e)))
+        .and_then(|out| {
+            if out.status.success() {
+                std::fs::read(&output).map_err(|e| PulseError::Io(format!("Failed to read converted PNG: {}", e)))
+            } else {
+                Err(PulseError::Encode(format!("sips failed to convert HEIC to PNG: {}", String::from_utf8_lossy(&out.stderr).trim())))
+            }
+        });
+
+    let _ = std::fs::remove_file(&input);
+    let _ = std::fs::remove_file(&output);
+
+    result
+}
+
+/// Upload image data to server with retry logic, optionally to a named backend
+/// and under a custom `filename`. Pass an `upload_id` to be able to cancel it.
+#[tauri::command(async)]
+pub fn upload_image(app: tauri::AppHandle, state: State<'_, AppState>, image_base64: String, retry_count: Option<u32>, backend: Option<String>, filename: Option<String>, upload_id: Option<String>) -> Result<UploadResult, PulseError> {
+    let cancel = start_cancellable_upload(&state, upload_id);
+    upload_image_with_retry(image_base64, backend.as_deref(), filename.as_deref(), retry_count.unwrap_or(0), &cancel).inspect(|result| finish_upload(&app, result))
+}
+
+/// Image types accepted from a data URL; HEIC is converted to PNG before upload
+const SUPPORTED_IMAGE_MIMES: [&str; 6] = ["image/png", "image/jpeg", "image/gif", "image/webp", "image/heic", "image/heif"];
+
+/// Split a `data:<mime>;base64,<data>` URL into its declared mime and payload.
+/// Plain base64 without the prefix has no declared mime.
+fn parse_data_url(input: &str) -> Result<(Option<String>, &str), PulseError> {
+    let Some(rest) = input.strip_prefix("data:") else {
+        return Ok((None, input));
     };
+    let (header, data) = rest
+        .split_once(',')
+        .ok_or_else(|| PulseError::Invalid("Malformed data URL: missing ','".to_string()))?;
+
+    let mut params = header.split(';');
+    let mime = params.next().unwrap_or_default().trim().to_ascii_lowercase();
+    if !params.any(|param| param.trim().eq_ignore_ascii_case("base64")) {
+        return Err(PulseError::Invalid("Data URL is not base64-encoded".to_string()));
+    }
+    // Browsers sometimes label JPEGs with the non-standard image/jpg
+    let mime = if mime == "image/jpg" { "image/jpeg".to_string() } else { mime };
+    if !SUPPORTED_IMAGE_MIMES.contains(&mime.as_str()) {
+        return Err(PulseError::UnsupportedImage(format!(
+            "Unsupported image type '{}'; expected one of {}",
+            mime,
+            SUPPORTED_IMAGE_MIMES.join(", ")
+        )));
+    }
+
+    Ok((Some(mime), data))
+}
 
-    let image_bytes = base64::engine::general_purpose::STANDARD
+/// Decode a data URL (or bare base64) into its declared mime and bytes
+fn decode_image_base64(input: &str) -> Result<(Option<String>, Vec<u8>), PulseError> {
+    let (declared_mime, base64_data) = parse_data_url(input)?;
+    let bytes = base64::engine::general_purpose::STANDARD
         .decode(base64_data)
         .map_err(|e| {
             log::error!("Failed to decode base64: {}", e);
-            format!("Failed to decode base64: {}", e)
+            PulseError::Invalid(format!("Invalid base64 image data: {}", e))
         })?;
+    Ok((declared_mime, bytes))
+}
 
-    let size_bytes = image_bytes.len();
+fn upload_image_with_retry(image_base64: String, backend: Option<&str>, filename: Option<&str>, retry_count: u32, cancel: &Arc<AtomicBool>) -> Result<UploadResult, PulseError> {
+    let (declared_mime, image_bytes) = decode_image_base64(&image_base64)?;
+
+    let image_bytes = if is_heic(&image_bytes) {
+        log::info!("HEIC image detected, converting to PNG");
+        heic_to_png(&image_bytes).map_err(|e| {
+            log::error!("{}", e);
+            e
+        })?
+    } else {
+        image_bytes
+    };
 
     if image_bytes.len() >= 8 {
         let header = &image_bytes[0..8];
@@ -92,37 +340,462 @@ fn upload_image_with_retry(image_base64: String, retry_count: u32) -> Result<Upl
             header[4], header[5], header[6], header[7]);
     }
 
-    log::info!("Uploading image: {} bytes, attempt {}", size_bytes, retry_count + 1);
+    let (mime, extension) = detect_image_type(&image_bytes).ok_or_else(|| {
+        log::error!("Decoded {} bytes are not a supported image", image_bytes.len());
+        PulseError::UnsupportedImage("Clipboard content is not a supported image".to_string())
+    })?;
+    // A data URL whose declared type disagrees with its content is rejected
+    // rather than uploaded under either label. HEIC was converted to PNG above.
+    if let Some(declared) = declared_mime.as_deref().filter(|declared| !matches!(*declared, "image/heic" | "image/heif")) {
+        if declared != mime {
+            log::error!("Data URL says {} but the content is {}", declared, mime);
+            return Err(PulseError::UnsupportedImage(format!("Data URL is labelled {} but contains {}", declared, mime)));
+        }
+    }
 
-    let part = reqwest::blocking::multipart::Part::bytes(image_bytes.clone())
-        .file_name("image.png")
-        .mime_str("image/png")
-        .map_err(|e| {
-            log::error!("Failed to create mime part: {}", e);
-            format!("Failed to create mime part: {}", e)
-        })?;
+    let file_name = filename
+        .and_then(|name| sanitize_filename(name, extension))
+        .unwrap_or_else(|| format!("image.{}", extension));
 
-    let form = reqwest::blocking::multipart::Form::new()
-        .part("file", part);
+    upload_bytes_dedup(image_bytes, &file_name, mime, backend, retry_count, cancel)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Upload unless identical bytes are already in the history (and `upload.dedup`
+/// is on), in which case the earlier URL comes back with `duplicate: true`
+fn upload_bytes_dedup(bytes: Vec<u8>, file_name: &str, mime: &str, backend: Option<&str>, retry_count: u32, cancel: &Arc<AtomicBool>) -> Result<UploadResult, PulseError> {
+    let config = load_config();
+    let sha256 = sha256_hex(&bytes);
+
+    if config.upload.dedup {
+        if let Some(entry) = find_upload_by_hash(&sha256) {
+            log::info!("Skipping upload of {}: same content as {}", file_name, entry.url);
+            return Ok(UploadResult {
+                success: true,
+                url: Some(entry.url),
+                filename: entry.filename,
+                size: entry.size,
+                duration: None,
+                upload_speed: None,
+                error: None,
+                raw_response: None,
+                duplicate: true,
+                sha256: Some(sha256),
+            });
+        }
+    }
+
+    let mut result = upload_bytes_with_retry(bytes, file_name, mime, backend, retry_count, Instant::now(), cancel)?;
+    result.sha256 = Some(sha256);
+    Ok(result)
+}
+
+/// Make a user-supplied file name safe for the multipart part: keep only the
+/// last path component, drop control characters and force `extension`.
+/// Returns None when nothing usable is left.
+fn sanitize_filename(name: &str, extension: &str) -> Option<String> {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let base: String = base.chars().filter(|c| !c.is_control()).collect();
+    let base = base.trim().trim_start_matches('.');
+
+    let stem = match base.rsplit_once('.') {
+        Some((stem, _)) if !extension.is_empty() => stem,
+        _ => base,
+    };
+    if stem.is_empty() {
+        return None;
+    }
+
+    if extension.is_empty() {
+        Some(stem.to_string())
+    } else {
+        Some(format!("{}.{}", stem, extension))
+    }
+}
+
+/// Upload a file from disk, detecting its mime type from content.
+/// A custom `filename` keeps the file's own extension.
+#[tauri::command(async)]
+pub fn upload_file(app: tauri::AppHandle, state: State<'_, AppState>, path: String, backend: Option<String>, filename: Option<String>, upload_id: Option<String>) -> Result<UploadResult, PulseError> {
+    let cancel = start_cancellable_upload(&state, upload_id);
+    upload_path(path, backend.as_deref(), filename, &cancel).inspect(|result| finish_upload(&app, result))
+}
+
+/// Capture the screen with macOS `screencapture` into a temp PNG: `region`,
+/// or an interactive selection when None. Dismissing the selection with Esc
+/// writes no file and returns `Cancelled`.
+fn capture_screenshot(region: Option<&Rect>) -> Result<PathBuf, PulseError> {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let output = std::env::temp_dir().join(format!("pulse-screenshot-{}-{}.png", std::process::id(), stamp));
+
+    let mut command = std::process::Command::new("screencapture");
+    command.args(["-x", "-t", "png"]);
+    match region {
+        Some(rect) => command.arg("-R").arg(format!("{},{},{},{}", rect.x, rect.y, rect.width, rect.height)),
+        None => command.arg("-i"),
+    };
+
+    let out = command
+        .arg(&output)
+        .output()
+        .map_err(|e| PulseError::Io(format!("Failed to execute screencapture: {}", e)))?;
+    if !out.status.success() {
+        let _ = fs::remove_file(&output);
+        return Err(PulseError::Io(format!("screencapture failed: {}", String::from_utf8_lossy(&out.stderr).trim())));
+    }
+    if !output.is_file() {
+        return Err(PulseError::Cancelled);
+    }
+    Ok(output)
+}
+
+/// Take a screenshot (interactive selection when `region` is None) and upload
+/// it. The temp file is removed whether or not the upload succeeds.
+#[tauri::command(async)]
+pub fn capture_and_upload(app: tauri::AppHandle, state: State<'_, AppState>, region: Option<Rect>, backend: Option<String>, upload_id: Option<String>) -> Result<UploadResult, PulseError> {
+    let path = capture_screenshot(region.as_ref())?;
+    let cancel = start_cancellable_upload(&state, upload_id);
+    let result = upload_path(path.to_string_lossy().to_string(), backend.as_deref(), Some("screenshot.png".to_string()), &cancel);
+    let _ = fs::remove_file(&path);
+    result.inspect(|result| finish_upload(&app, result))
+}
+
+/// Upload files dropped on the window one by one, emitting an indexed
+/// `upload-result` per file. Per-file failures are skipped; auth errors and
+/// cancelling stop the batch since every later file would fail the same way.
+#[tauri::command(async)]
+pub fn upload_dropped_files(app: tauri::AppHandle, state: State<'_, AppState>, paths: Vec<String>, backend: Option<String>, upload_id: Option<String>) -> Result<Vec<UploadResult>, PulseError> {
+    // A missing or unknown backend fails every file, so check it once up front
+    load_config().upload.resolve_backend(backend.as_deref())?;
+
+    let cancel = start_cancellable_upload(&state, upload_id);
+    let mut results = Vec::with_capacity(paths.len());
+
+    for (index, path) in paths.into_iter().enumerate() {
+        let (result, stop) = match upload_path(path.clone(), backend.as_deref(), None, &cancel) {
+            Ok(result) => {
+                finish_upload(&app, &result);
+                (result, false)
+            }
+            Err(err) => {
+                log::warn!("Upload of dropped file {} failed: {}", path, err);
+                let stop = matches!(err, PulseError::Api { status: 401 | 403, .. } | PulseError::Cancelled);
+                (UploadResult {
+                    success: false,
+                    url: None,
+                    filename: None,
+                    size: None,
+                    duration: None,
+                    upload_speed: None,
+                    error: Some(err.to_string()),
+                    raw_response: err.raw_response().map(str::to_string),
+                    duplicate: false,
+                    sha256: None,
+                }, stop)
+            }
+        };
+
+        emit_upload_result(&app, result.clone(), Some(index));
+        results.push(result);
+        if stop {
+            log::warn!("Stopping dropped file upload after file {}", index);
+            break;
+        }
+    }
+
+    Ok(results)
+}
+
+fn upload_path(path: String, backend: Option<&str>, filename: Option<String>, cancel: &Arc<AtomicBool>) -> Result<UploadResult, PulseError> {
+    let config = load_config();
+    let path = PathBuf::from(path);
+
+    let metadata = fs::metadata(&path).map_err(|e| PulseError::Io(format!("Failed to read file {:?}: {}", path, e)))?;
+    if !metadata.is_file() {
+        return Err(PulseError::Io(format!("Not a file: {:?}", path)));
+    }
+
+    let max_bytes = config.upload.max_file_size_mb.saturating_mul(1024 * 1024);
+    if metadata.len() > max_bytes {
+        return Err(PulseError::Config(format!(
+            "File is too large: {} exceeds the {} MB limit (upload.max_file_size_mb)",
+            format_size(metadata.len() as usize, &FormatOptions::from_config(&config.general)),
+            config.upload.max_file_size_mb
+        )));
+    }
+
+    let bytes = fs::read(&path).map_err(|e| PulseError::Io(format!("Failed to read file {:?}: {}", path, e)))?;
+    let mime = infer::get(&bytes).map(|kind| kind.mime_type()).unwrap_or("application/octet-stream");
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let file_name = filename
+        .as_deref()
+        .and_then(|name| sanitize_filename(name, &extension))
+        .or_else(|| path.file_name().map(|name| name.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "file".to_string());
+
+    log::info!("Uploading file {:?} ({})", path, mime);
+    upload_bytes_dedup(bytes, &file_name, mime, backend, 0, cancel)
+}
+
+/// Check the upload config by sending a 1x1 PNG. The result is not recorded
+/// in history or copied to the clipboard.
+#[tauri::command]
+pub fn test_upload_config(backend: Option<String>) -> UploadTestResult {
+    let started = Instant::now();
+    let result = encode_image(&[0, 0, 0, 0], 1, 1, UploadFormat::Png, 100, None).and_then(|png| {
+        // Separate cancel flag so a test doesn't interfere with a real upload
+        let cancel = Arc::new(AtomicBool::new(false));
+        upload_bytes_with_retry(png, "pulse-test.png", "image/png", backend.as_deref(), 0, started, &cancel)
+    });
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(result) => UploadTestResult {
+            success: true,
+            status: None,
+            duration_ms,
+            url: result.url,
+            message: "Upload server is working".to_string(),
+        },
+        Err(err) => {
+            let (status, message) = match &err {
+                PulseError::Api { status: status @ (401 | 403), .. } => {
+                    (Some(*status), format!("Authentication failed ({}): check the upload token", status))
+                }
+                PulseError::Api { status, message, .. } => (Some(*status), message.clone()),
+                PulseError::Network(message) => (None, format!("Could not reach upload server: {}", message)),
+                other => (None, other.to_string()),
+            };
+            log::warn!("Upload config test failed: {}", message);
+            UploadTestResult { success: false, status, duration_ms, url: None, message }
+        }
+    }
+}
+
+/// Stop the upload started with `upload_id`; it fails with a `cancelled` error
+/// and is not retried. Returns false when no such upload is running.
+#[tauri::command(async)]
+pub fn cancel_upload(state: State<'_, AppState>, upload_id: String) -> bool {
+    match state.upload_cancels.lock_or_recover().get(&upload_id) {
+        Some(cancel) => {
+            log::info!("Upload cancel requested for {}", upload_id);
+            cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => {
+            log::info!("No running upload {} to cancel", upload_id);
+            false
+        }
+    }
+}
+
+/// Source of ids for uploads started without one (e.g. from a shortcut)
+static NEXT_UPLOAD_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// A running upload: derefs to its own cancel flag, registered under its id
+/// and counted toward `uploads_in_flight` until dropped
+struct UploadSession {
+    id: String,
+    cancel: Arc<AtomicBool>,
+    cancels: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Deref for UploadSession {
+    type Target = Arc<AtomicBool>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.cancel
+    }
+}
+
+impl Drop for UploadSession {
+    fn drop(&mut self) {
+        let mut cancels = self.cancels.lock_or_recover();
+        // A later upload may have reused the id; leave its flag alone
+        if cancels.get(&self.id).is_some_and(|cancel| Arc::ptr_eq(cancel, &self.cancel)) {
+            cancels.remove(&self.id);
+        }
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Register a fresh cancel flag for a new upload under `upload_id`, or a
+/// generated id when the caller didn't pick one
+fn start_cancellable_upload(state: &AppState, upload_id: Option<String>) -> UploadSession {
+    let id = upload_id.unwrap_or_else(|| format!("upload-{}", NEXT_UPLOAD_ID.fetch_add(1, Ordering::Relaxed)));
+    let cancel = Arc::new(AtomicBool::new(false));
+    state.upload_cancels.lock_or_recover().insert(id.clone(), cancel.clone());
+    state.uploads_in_flight.fetch_add(1, Ordering::Relaxed);
+    UploadSession {
+        id,
+        cancel,
+        cancels: state.upload_cancels.clone(),
+        in_flight: state.uploads_in_flight.clone(),
+    }
+}
+
+/// Request body reader that aborts once the cancel flag is set
+struct CancellableReader {
+    inner: Cursor<Vec<u8>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Read for CancellableReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(io::Error::other("Upload cancelled"));
+        }
+        self.inner.read(buf)
+    }
+}
+
+/// Shared upload client, kept across uploads and retries so connections are
+/// reused. Cleared by `reset_upload_client` when config.toml changes.
+static UPLOAD_CLIENT: RwLock<Option<reqwest::blocking::Client>> = RwLock::new(None);
 
-    let client = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(60))
+fn upload_client(config: &Config) -> Result<reqwest::blocking::Client, PulseError> {
+    if let Some(client) = UPLOAD_CLIENT.read().unwrap().as_ref() {
+        return Ok(client.clone());
+    }
+
+    let mut client_builder = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(config.upload.timeout_secs.max(1)));
+    if let Some(proxy) = config.general.proxy()? {
+        client_builder = client_builder.proxy(proxy);
+    }
+    if let Some(cert) = config.upload.ca_certificate()? {
+        client_builder = client_builder.add_root_certificate(cert);
+    }
+    if config.upload.danger_accept_invalid_certs {
+        log::warn!("upload.danger_accept_invalid_certs is set: TLS certificates are NOT verified for uploads");
+        client_builder = client_builder.danger_accept_invalid_certs(true);
+    }
+    let client = client_builder
         .build()
         .map_err(|e| {
             log::error!("Failed to create HTTP client: {}", e);
-            format!("Failed to create HTTP client: {}", e)
+            PulseError::Network(format!("Failed to create HTTP client: {}", e))
         })?;
 
-    log::info!("Sending PUT request to {}", url);
-    let response = client
-        .put(url)
-        .header("Authorization", format!("Bearer {}", config.upload.token))
+    *UPLOAD_CLIENT.write().unwrap() = Some(client.clone());
+    Ok(client)
+}
+
+/// Drop the shared upload client so the next upload picks up new timeout/proxy/TLS settings
+pub fn reset_upload_client() {
+    *UPLOAD_CLIENT.write().unwrap() = None;
+}
+
+/// Validate one configured header name/value pair
+pub fn parse_header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| format!("Invalid header name '{}'", name))?;
+    let header_value = HeaderValue::from_str(value).map_err(|_| format!("Invalid value for header '{}'", name))?;
+    Ok((header_name, header_value))
+}
+
+/// Bearer token (when set) plus `upload.headers`; invalid headers are skipped
+fn upload_headers(token: &str, extra: &HashMap<String, String>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if !token.is_empty() {
+        match HeaderValue::from_str(&format!("Bearer {}", token)) {
+            Ok(value) => {
+                headers.insert(AUTHORIZATION, value);
+            }
+            Err(_) => log::warn!("Upload token contains invalid header characters, not sending it"),
+        }
+    }
+    for (name, value) in extra {
+        match parse_header(name, value) {
+            Ok((name, value)) => {
+                headers.insert(name, value);
+            }
+            Err(e) => log::warn!("{}, skipping", e),
+        }
+    }
+    headers
+}
+
+/// Longest server response kept in results and errors
+const RAW_RESPONSE_LIMIT: usize = 4096;
+
+/// Cut a response body to RAW_RESPONSE_LIMIT bytes on a char boundary
+fn truncate_response(text: &str) -> String {
+    if text.len() <= RAW_RESPONSE_LIMIT {
+        return text.to_string();
+    }
+    let mut end = RAW_RESPONSE_LIMIT;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}… ({} bytes total)", &text[..end], text.len())
+}
+
+/// `started` marks the first attempt so the reported duration spans all retries
+fn upload_bytes_with_retry(bytes: Vec<u8>, file_name: &str, mime: &str, backend: Option<&str>, retry_count: u32, started: Instant, cancel: &Arc<AtomicBool>) -> Result<UploadResult, PulseError> {
+    upload_bytes_with_config(&load_config(), bytes, file_name, mime, backend, retry_count, started, cancel)
+}
+
+/// upload_bytes_with_retry against an already loaded config, which stays the
+/// same across retries
+#[allow(clippy::too_many_arguments)]
+fn upload_bytes_with_config(config: &Config, bytes: Vec<u8>, file_name: &str, mime: &str, backend: Option<&str>, retry_count: u32, started: Instant, cancel: &Arc<AtomicBool>) -> Result<UploadResult, PulseError> {
+    if cancel.load(Ordering::Relaxed) {
+        log::info!("Upload cancelled");
+        return Err(PulseError::Cancelled);
+    }
+
+    let backend_config = config.upload.resolve_backend(backend)?;
+
+    let url = &backend_config.url;
+    let size_bytes = bytes.len();
+
+    log::info!("Uploading {}: {} bytes, attempt {}", file_name, size_bytes, retry_count + 1);
+
+    let reader = CancellableReader {
+        inner: Cursor::new(bytes.clone()),
+        cancel: cancel.clone(),
+    };
+    let part = reqwest::blocking::multipart::Part::reader_with_length(reader, size_bytes as u64)
+        .file_name(file_name.to_string())
+        .mime_str(mime)
+        .map_err(|e| {
+            log::error!("Failed to create mime part: {}", e);
+            PulseError::Encode(format!("Failed to create mime part: {}", e))
+        })?;
+
+    let form = reqwest::blocking::multipart::Form::new()
+        .part(config.upload.field_name.clone(), part);
+
+    let client = upload_client(config)?;
+
+    let request = match config.upload.method {
+        UploadMethod::Put => client.put(url),
+        UploadMethod::Post => client.post(url),
+    };
+    log::info!("Sending {} request to {}", config.upload.method.as_str(), url);
+    let response = request
+        .headers(upload_headers(&backend_config.token, &config.upload.headers))
         .multipart(form)
         .send();
 
     match response {
         Ok(resp) => {
             let status = resp.status();
+            let server_delay = resp
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(retry_after);
             let response_text = resp.text().unwrap_or_else(|_| "Unable to decode response".to_string());
 
             log::info!("Upload response status: {}", status);
@@ -132,153 +805,489 @@ fn upload_image_with_retry(image_base64: String, retry_count: u32) -> Result<Upl
                 if let Ok(json) = serde_json::from_str::<serde_json::Value>(&response_text) {
                     log::info!("Parsed JSON response: {}", json);
 
-                    if let Some(url_path) = json["url"].as_str() {
+                    if let Some(url_path) = json_path(&json, &config.upload.response_url_path).and_then(|v| v.as_str()) {
                         log::info!("url_path from API: {}", url_path);
-                        let base = if config.upload.base_url.is_empty() {
+                        let base = if backend_config.base_url.is_empty() {
                             url.trim_end_matches("/api/image").to_string()
                         } else {
-                            config.upload.base_url.clone()
+                            backend_config.base_url.clone()
+                        };
+                        // Some hosts return an absolute link rather than a path
+                        let full_url = if url_path.starts_with("http://") || url_path.starts_with("https://") {
+                            url_path.to_string()
+                        } else {
+                            format!("{}{}", base, url_path)
                         };
-                        let full_url = format!("{}{}", base, url_path);
                         log::info!("Final image URL: {}", full_url);
 
-                        let filename = json["originalFileName"].as_str().unwrap_or("image.png");
-                        let size = crate::modules::utils::format_size(size_bytes);
-                        return Ok(UploadResult {
+                        let filename = json_path(&json, &config.upload.response_filename_path)
+                            .and_then(|v| v.as_str())
+                            .unwrap_or(file_name);
+                        let size = format_size(size_bytes, &FormatOptions::from_config(&config.general));
+                        let elapsed = started.elapsed();
+                        let duration = crate::modules::utils::format_duration(elapsed);
+                        let upload_speed = (elapsed.as_secs_f64() > 0.0).then(|| {
+                            let bytes_per_sec = (size_bytes as f64 / elapsed.as_secs_f64()) as u64;
+                            format_speed(bytes_per_sec, &FormatOptions::from_config(&config.general)).trim().to_string()
+                        });
+                        Ok(UploadResult {
                             success: true,
                             url: Some(full_url),
                             filename: Some(filename.to_string()),
                             size: Some(size),
-                            duration: None,
+                            duration: Some(duration),
+                            upload_speed,
                             error: None,
-                        });
+                            raw_response: Some(truncate_response(&response_text)),
+                            duplicate: false,
+                            sha256: None,
+                        })
                     } else {
-                        log::error!("No 'url' field in response");
-                        return Err(format!("No 'url' field in response: {}", response_text));
+                        log::error!("No '{}' field in response", config.upload.response_url_path);
+                        Err(PulseError::BadResponse {
+                            message: format!("No '{}' field in response: {}", config.upload.response_url_path, truncate_response(&response_text)),
+                            raw_response: truncate_response(&response_text),
+                        })
                     }
                 } else {
                     log::error!("Failed to parse JSON response");
-                    return Err(format!("Failed to parse JSON: {}", response_text));
+                    Err(PulseError::BadResponse {
+                        message: format!("Failed to parse JSON: {}", truncate_response(&response_text)),
+                        raw_response: truncate_response(&response_text),
+                    })
                 }
-            } else if (status.is_server_error() || status == 429) && retry_count < 2 {
-                log::warn!("Server error, retrying... status: {}", status);
-                thread::sleep(Duration::from_secs(1));
-                upload_image_with_retry(image_base64, retry_count + 1)
+            } else if (status.is_server_error() || status == 429) && retry_count < config.upload.max_retries {
+                // Rate-limited hosts say how long to wait; otherwise back off
+                let delay = server_delay.unwrap_or_else(|| backoff_delay(retry_count));
+                log::warn!("Server error, retrying in {:?}... status: {}", delay, status);
+                thread::sleep(delay);
+                upload_bytes_with_config(config, bytes, file_name, mime, backend, retry_count + 1, started, cancel)
             } else {
                 log::error!("Upload failed with status {}: {}", status, response_text);
-                Err(format!("Upload failed with status {}: {}", status, response_text))
+                Err(PulseError::Api {
+                    status: status.as_u16(),
+                    message: format!("Upload failed with status {}: {}", status, truncate_response(&response_text)),
+                    raw_response: Some(truncate_response(&response_text)),
+                })
             }
         }
+        Err(_) if cancel.load(Ordering::Relaxed) => {
+            log::info!("Upload cancelled");
+            Err(PulseError::Cancelled)
+        }
         Err(e) => {
-            if e.is_timeout() || e.is_connect() && retry_count < 2 {
-                log::warn!("Network error, retrying: {}", e);
-                thread::sleep(Duration::from_secs(1));
-                upload_image_with_retry(image_base64, retry_count + 1)
+            if (e.is_timeout() || e.is_connect()) && retry_count < config.upload.max_retries {
+                let delay = backoff_delay(retry_count);
+                log::warn!("Network error, retrying in {:?}: {}", delay, e);
+                thread::sleep(delay);
+                upload_bytes_with_config(config, bytes, file_name, mime, backend, retry_count + 1, started, cancel)
             } else {
                 log::error!("Network error: {}", e);
-                Err(format!("Network error: {}", e))
+                Err(PulseError::Network(format!("Network error: {}", e)))
             }
         }
     }
 }
 
+/// List configured upload backends for the UI (tokens are never exposed)
+#[tauri::command]
+pub fn list_upload_backends() -> Vec<UploadBackendInfo> {
+    let upload = load_config().upload;
+    let default_name = upload.resolve_backend(None).ok().map(|b| b.name);
+
+    upload
+        .backends
+        .iter()
+        .map(|backend| UploadBackendInfo {
+            name: backend.name.clone(),
+            url: backend.url.clone(),
+            is_default: default_name.as_deref() == Some(backend.name.as_str()),
+        })
+        .collect()
+}
+
+/// Post-success steps shared by all upload entry points
+fn finish_upload(app: &tauri::AppHandle, result: &UploadResult) {
+    record_upload(result);
+    update_copy_url_item(app);
+    if load_config().upload.copy_url_to_clipboard {
+        if let Some(url) = result.url.as_ref() {
+            copy_url_to_clipboard(url);
+        }
+    }
+}
+
+/// Write an uploaded URL to the clipboard; failures are logged, not fatal
+fn copy_url_to_clipboard(url: &str) {
+    match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(url)) {
+        Ok(()) => log::info!("Copied upload URL to clipboard"),
+        Err(e) => log::warn!("Failed to copy upload URL to clipboard: {}", e),
+    }
+}
+
+/// Tray "Copy Last URL" action
+pub fn copy_last_upload_url() {
+    match last_upload_url() {
+        Some(url) => copy_url_to_clipboard(&url),
+        None => log::info!("No upload URL to copy"),
+    }
+}
+
+/// Native notification for an upload result, so shortcut uploads give feedback
+/// even when the window isn't focused
+fn notify_upload_result(handle: &tauri::AppHandle, result: &UploadResult) {
+    let (title, body) = if result.success {
+        ("Upload complete", result.url.clone().unwrap_or_default())
+    } else {
+        ("Upload failed", result.error.clone().unwrap_or_default())
+    };
+    if let Err(e) = handle.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show upload notification: {}", e);
+    }
+}
+
+/// Emit `upload-result`, shaped by the configured `upload.result_verbosity`.
+/// This is the single exit for shortcut and dropped-file uploads, so it also
+/// sends the notification. `index` is the file's position in a dropped batch.
+fn emit_upload_result(handle: &tauri::AppHandle, result: UploadResult, index: Option<usize>) {
+    let upload_config = load_config().upload;
+    if upload_config.notify {
+        notify_upload_result(handle, &result);
+    }
+    let _ = match upload_config.result_verbosity {
+        ResultVerbosity::Full => handle.emit("upload-result", IndexedUploadResult { index, result }),
+        ResultVerbosity::Minimal => handle.emit("upload-result", IndexedUploadResult { index, result: UploadResultMinimal::from(result) }),
+    };
+}
+
+/// Upload shortcut bytes, then bring up the window and report the result
+fn upload_shortcut_bytes(handle: &tauri::AppHandle, bytes: Vec<u8>, file_name: &str, mime: &str) {
+    log::info!("Starting upload...");
+    let cancel = start_cancellable_upload(&handle.state::<AppState>(), None);
+    // The id is generated here, so hand it to the frontend's cancel button
+    let _ = handle.emit("upload-started", &cancel.id);
+    match upload_bytes_dedup(bytes, file_name, mime, None, 0, &cancel) {
+        Ok(result) => {
+            log::info!("Upload successful: {:?}", result);
+            finish_upload(handle, &result);
+
+            if let Some(window) = show_main_window(handle) {
+                thread::sleep(Duration::from_millis(50));
+                let _ = window.emit("switch-to-upload", ());
+            }
+            thread::sleep(Duration::from_millis(50));
+            emit_upload_result(handle, result, None);
+        }
+        Err(err) => {
+            log::error!("Upload failed: {}", err);
+
+            if let Some(window) = show_main_window(handle) {
+                thread::sleep(Duration::from_millis(50));
+                let _ = window.emit("switch-to-upload", ());
+            }
+            thread::sleep(Duration::from_millis(50));
+            emit_upload_result(handle, UploadResult {
+                success: false,
+                url: None,
+                filename: None,
+                size: None,
+                duration: None,
+                upload_speed: None,
+                error: Some(err.to_string()),
+                raw_response: err.raw_response().map(str::to_string),
+                duplicate: false,
+                sha256: None,
+            }, None);
+        }
+    }
+}
+
+/// Global shortcut handler: interactive screenshot, then the same upload
+/// flow as the clipboard shortcut
+pub fn handle_capture_shortcut(handle: tauri::AppHandle) {
+    log::info!("Global shortcut triggered: capture");
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = match capture_screenshot(None) {
+            Ok(path) => path,
+            Err(PulseError::Cancelled) => {
+                log::info!("Screenshot selection dismissed");
+                return;
+            }
+            Err(e) => {
+                log::error!("Screenshot failed: {}", e);
+                return;
+            }
+        };
+        let bytes = fs::read(&path);
+        let _ = fs::remove_file(&path);
+        match bytes {
+            Ok(bytes) => upload_shortcut_bytes(&handle, bytes, "screenshot.png", "image/png"),
+            Err(e) => log::error!("Failed to read screenshot {:?}: {}", path, e),
+        }
+    });
+}
+
 /// Handle global shortcut trigger for image upload
 pub fn handle_upload_shortcut(handle: tauri::AppHandle) {
-    log::info!("Global shortcut triggered: Shift+Cmd+U");
+    log::info!("Global shortcut triggered: upload");
     tauri::async_runtime::spawn_blocking(move || {
         log::info!("Accessing clipboard...");
+        // Animated GIFs would lose all but the first frame through arboard's RGBA
+        if let Some(gif) = clipboard_gif() {
+            log::info!("Got GIF from clipboard: {} bytes, uploading as-is", gif.len());
+            upload_shortcut_bytes(&handle, gif, "image.gif", "image/gif");
+            return;
+        }
         if let Ok(mut clipboard) = Clipboard::new() {
             if let Ok(image_data) = clipboard.get_image() {
                 log::info!("Got image from clipboard: {} bytes, {}x{}", image_data.bytes.len(), image_data.width, image_data.height);
 
-                let png_bytes = match rgba_to_png(&image_data.bytes, image_data.width, image_data.height) {
+                let upload_config = load_config().upload;
+                let format = upload_config.format;
+
+                let image_bytes = match encode_image(&image_data.bytes, image_data.width, image_data.height, format, upload_config.quality, upload_config.optimize_level()) {
                     Ok(data) => {
-                        log::info!("Converted to PNG: {} bytes", data.len());
+                        log::info!("Converted to {}: {} bytes", format.extension(), data.len());
                         if data.len() >= 8 {
                             let header = &data[0..8];
-                            log::info!("PNG header bytes: {:02x} {:02x} {:02x} {:02x} {:02x} {:02x} {:02x} {:02x}",
+                            log::info!("Image header bytes: {:02x} {:02x} {:02x} {:02x} {:02x} {:02x} {:02x} {:02x}",
                                 header[0], header[1], header[2], header[3],
                                 header[4], header[5], header[6], header[7]);
                         }
                         data
                     }
                     Err(e) => {
-                        log::error!("Failed to convert to PNG: {}", e);
-                        let _ = handle.emit("upload-result", UploadResult {
+                        log::error!("Failed to convert image: {}", e);
+                        emit_upload_result(&handle, UploadResult {
                             success: false,
                             url: None,
                             filename: None,
                             size: None,
                             duration: None,
+                            upload_speed: None,
                             error: Some(format!("Failed to convert image: {}", e)),
-                        });
-                        if let Some(window) = handle.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
+                            raw_response: None,
+                            duplicate: false,
+                            sha256: None,
+                        }, None);
+                        if let Some(window) = show_main_window(&handle) {
                             let _ = window.emit("switch-to-upload", ());
                         }
                         return;
                     }
                 };
 
-                let base64_data = base64::engine::general_purpose::STANDARD.encode(&png_bytes);
-                let data_url = format!("data:image/png;base64,{}", base64_data);
-
-                log::info!("Starting upload...");
-                match upload_image_with_retry(data_url, 0) {
-                    Ok(result) => {
-                        log::info!("Upload successful: {:?}", result);
-
-                        if let Some(window) = handle.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            thread::sleep(Duration::from_millis(50));
-                            let _ = window.emit("switch-to-upload", ());
-                        }
-                        thread::sleep(Duration::from_millis(50));
-                        let _ = handle.emit("upload-result", result);
-                    }
-                    Err(err) => {
-                        log::error!("Upload failed: {}", err);
-
-                        if let Some(window) = handle.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            thread::sleep(Duration::from_millis(50));
-                            let _ = window.emit("switch-to-upload", ());
-                        }
-                        thread::sleep(Duration::from_millis(50));
-                        let _ = handle.emit("upload-result", UploadResult {
-                            success: false,
-                            url: None,
-                            filename: None,
-                            size: None,
-                            duration: None,
-                            error: Some(err),
-                        });
-                    }
-                }
+                let file_name = format!("image.{}", format.extension());
+                upload_shortcut_bytes(&handle, image_bytes, &file_name, format.mime());
             } else {
                 log::warn!("No image in clipboard");
 
-                if let Some(window) = handle.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
+                if let Some(window) = show_main_window(&handle) {
                     thread::sleep(Duration::from_millis(50));
                     let _ = window.emit("switch-to-upload", ());
                 }
                 thread::sleep(Duration::from_millis(50));
-                let _ = handle.emit("upload-result", UploadResult {
+                emit_upload_result(&handle, UploadResult {
                     success: false,
                     url: None,
                     filename: None,
                     size: None,
                     duration: None,
+                    upload_speed: None,
                     error: Some("No image in clipboard".to_string()),
-                });
+                    raw_response: None,
+                    duplicate: false,
+                    sha256: None,
+                }, None);
             }
         } else {
             log::error!("Failed to access clipboard");
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Deterministic RGBA test pattern
+    fn pattern(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height * 4).map(|i| (i * 7 % 256) as u8).collect()
+    }
+
+    #[test]
+    fn optimize_png_is_lossless() {
+        let rgba = pattern(8, 5);
+        let png = encode_image(&rgba, 8, 5, UploadFormat::Png, 100, None).unwrap();
+        let optimized = optimize_png(png, 4);
+
+        let decoded = image::load_from_memory(&optimized).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (8, 5));
+        assert_eq!(decoded.into_raw(), rgba);
+    }
+
+    #[test]
+    fn unpadded_rgba_crops_padded_rows() {
+        let rgba = pattern(2, 2);
+        assert_eq!(unpadded_rgba(&rgba, 2, 2).unwrap(), rgba);
+
+        // 12-byte stride for 8 bytes of pixels per row
+        let padded: Vec<u8> = rgba.chunks(8).flat_map(|row| row.iter().copied().chain([0; 4])).collect();
+        assert_eq!(unpadded_rgba(&padded, 2, 2).unwrap(), rgba);
+    }
+
+    #[test]
+    fn unpadded_rgba_rejects_mismatched_dimensions() {
+        // Not a whole number of rows
+        assert!(matches!(unpadded_rgba(&[0; 15], 2, 2), Err(PulseError::Encode(_))));
+        // Rows shorter than width * 4
+        assert!(matches!(unpadded_rgba(&[0; 10], 2, 2), Err(PulseError::Encode(_))));
+        assert!(matches!(unpadded_rgba(&[], 2, 2), Err(PulseError::Encode(_))));
+        assert!(matches!(unpadded_rgba(&[0; 16], 2, 0), Err(PulseError::Encode(_))));
+        assert!(matches!(unpadded_rgba(&[0; 16], usize::MAX, 2), Err(PulseError::Encode(_))));
+        assert!(encode_image(&[0; 15], 2, 2, UploadFormat::Png, 100, None).is_err());
+    }
+
+    #[test]
+    fn detect_image_type_rejects_short_input() {
+        assert_eq!(detect_image_type(b""), None);
+        assert_eq!(detect_image_type(b"\x89PNG"), None);
+        assert_eq!(detect_image_type(b"RIFF\0\0\0\0WEB"), None);
+        assert_eq!(detect_image_type(b"GIF89a"), Some(("image/gif", "gif")));
+    }
+
+    #[test]
+    fn parse_data_url_rejects_malformed_input() {
+        assert!(matches!(parse_data_url("data:image/png;base64"), Err(PulseError::Invalid(_))));
+        assert!(matches!(parse_data_url("data:image/png,iVBORw0KGgo="), Err(PulseError::Invalid(_))));
+        assert!(matches!(parse_data_url("data:text/plain;base64,aGk="), Err(PulseError::UnsupportedImage(_))));
+        assert_eq!(parse_data_url("data:image/jpg;base64,/9j/").unwrap(), (Some("image/jpeg".to_string()), "/9j/"));
+    }
+
+    #[test]
+    fn invalid_base64_is_an_error() {
+        assert!(matches!(decode_image_base64("data:image/png;base64,not base64!"), Err(PulseError::Invalid(_))));
+        // Cut mid-quantum, so the padding is wrong
+        assert!(matches!(decode_image_base64("data:image/png;base64,iVBORw0KG"), Err(PulseError::Invalid(_))));
+    }
+
+    #[test]
+    fn truncated_image_is_unsupported() {
+        // Only the first 6 bytes of the PNG signature survive
+        let (_, bytes) = decode_image_base64("data:image/png;base64,iVBORw0K").unwrap();
+        assert_eq!(bytes, b"\x89PNG\r\n");
+        assert_eq!(detect_image_type(&bytes), None);
+
+        // Rejected before anything is sent
+        let cancel = Arc::new(AtomicBool::new(false));
+        let result = upload_image_with_retry("data:image/png;base64,iVBORw0K".to_string(), None, None, 0, &cancel);
+        assert!(matches!(result, Err(PulseError::UnsupportedImage(_))));
+    }
+
+    #[test]
+    fn mislabeled_data_url_is_rejected() {
+        // A GIF header declared as PNG
+        let cancel = Arc::new(AtomicBool::new(false));
+        let result = upload_image_with_retry("data:image/png;base64,R0lGODlhAQABAAAAACw=".to_string(), None, None, 0, &cancel);
+        assert!(matches!(result, Err(PulseError::UnsupportedImage(_))));
+    }
+
+    fn mock_config(server: &MockServer) -> Config {
+        let mut config = Config::default();
+        config.upload.url = format!("{}/api/image", server.uri());
+        config.upload.token = "test-token".to_string();
+        config
+    }
+
+    async fn upload_to(server: &MockServer) -> Result<UploadResult, PulseError> {
+        upload_with(mock_config(server)).await
+    }
+
+    /// Run the blocking upload off the runtime so the mock server keeps serving
+    async fn upload_with(config: Config) -> Result<UploadResult, PulseError> {
+        tokio::task::spawn_blocking(move || {
+            let cancel = Arc::new(AtomicBool::new(false));
+            upload_bytes_with_config(&config, b"\x89PNG\r\n\x1a\n".to_vec(), "image.png", "image/png", None, 0, Instant::now(), &cancel)
+        })
+        .await
+        .unwrap()
+    }
+
+    fn ok_response() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({ "url": "/i/abc.png", "originalFileName": "abc.png" }))
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn upload_success() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT")).and(path("/api/image")).respond_with(ok_response()).expect(1).mount(&server).await;
+
+        let result = upload_to(&server).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.url, Some(format!("{}/i/abc.png", server.uri())));
+        assert_eq!(result.filename.as_deref(), Some("abc.png"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn upload_retries_server_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT")).respond_with(ResponseTemplate::new(500)).up_to_n_times(1).expect(1).mount(&server).await;
+        Mock::given(method("PUT")).respond_with(ok_response()).expect(1).mount(&server).await;
+
+        let result = upload_to(&server).await.unwrap();
+        assert!(result.success);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn upload_stops_after_max_retries() {
+        let server = MockServer::start().await;
+        let mut config = mock_config(&server);
+        config.upload.max_retries = 1;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(u64::from(config.upload.max_retries) + 1)
+            .mount(&server)
+            .await;
+
+        let result = upload_with(config).await;
+        assert!(matches!(result, Err(PulseError::Api { status: 500, .. })));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn upload_does_not_retry_client_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT")).respond_with(ResponseTemplate::new(404)).expect(1).mount(&server).await;
+
+        let result = upload_to(&server).await;
+        assert!(matches!(result, Err(PulseError::Api { status: 404, .. })));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn upload_honors_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "2"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT")).respond_with(ok_response()).expect(1).mount(&server).await;
+
+        let started = Instant::now();
+        let result = upload_to(&server).await.unwrap();
+        assert!(result.success);
+        // The first backoff step is at most 1.25s, so this is the server's delay
+        assert!(started.elapsed() >= Duration::from_secs(2));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn upload_rejects_malformed_json() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT")).respond_with(ResponseTemplate::new(200).set_body_string("<html>oops")).expect(1).mount(&server).await;
+
+        let result = upload_to(&server).await;
+        assert!(matches!(result, Err(PulseError::BadResponse { .. })));
+    }
+}
@@ -1,43 +1,399 @@
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+#[cfg(target_os = "macos")]
 use objc2::rc::Retained;
+#[cfg(target_os = "macos")]
 use objc2_app_kit::NSColor;
+#[cfg(target_os = "macos")]
+use objc2_foundation::NSLocale;
+use crate::modules::LocaleInfo;
+use crate::modules::config::{GeneralConfig, UnitStyle};
 
-pub fn format_speed(bytes: u64) -> String {
-    if bytes < 1024 {
+/// Number formatting options for the size/speed helpers
+#[derive(Debug, Clone, Copy)]
+pub struct FormatOptions {
+    pub decimal_separator: char,
+    pub units: UnitStyle,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self { decimal_separator: '.', units: UnitStyle::Compact }
+    }
+}
+
+impl FormatOptions {
+    /// Use the macOS locale's decimal separator when `general.locale_format` is set,
+    /// en-style formatting otherwise
+    pub fn from_config(general: &GeneralConfig) -> Self {
+        let decimal_separator = if general.locale_format {
+            current_locale().decimal_separator
+        } else {
+            '.'
+        };
+        Self { decimal_separator, units: general.units }
+    }
+
+    fn localize(&self, formatted: String) -> String {
+        if self.decimal_separator == '.' {
+            formatted
+        } else {
+            formatted.replace('.', &self.decimal_separator.to_string())
+        }
+    }
+}
+
+/// Mutex locking that survives a panic in another lock holder
+pub trait LockExt<T> {
+    /// Lock, recovering the guard if the mutex was poisoned, so one panic
+    /// doesn't permanently break every later caller (e.g. the tray loop)
+    fn lock_or_recover(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(|poisoned| {
+            log::warn!("Recovering poisoned lock on {}", std::any::type_name::<T>());
+            poisoned.into_inner()
+        })
+    }
+}
+
+/// Current macOS locale identifier and decimal separator
+#[cfg(target_os = "macos")]
+pub fn current_locale() -> LocaleInfo {
+    let locale = NSLocale::currentLocale();
+    let identifier = locale.localeIdentifier().to_string();
+    let decimal_separator = locale.decimalSeparator().to_string().chars().next().unwrap_or('.');
+    LocaleInfo { identifier, decimal_separator }
+}
+
+/// Locale from `LANG` (e.g. "de_DE.UTF-8"); the decimal separator isn't
+/// available without the system APIs, so it stays '.'
+#[cfg(not(target_os = "macos"))]
+pub fn current_locale() -> LocaleInfo {
+    let identifier = std::env::var("LANG")
+        .ok()
+        .and_then(|lang| lang.split('.').next().map(str::to_string))
+        .filter(|lang| !lang.is_empty() && lang != "C" && lang != "POSIX")
+        .unwrap_or_else(|| "en_US".to_string());
+    LocaleInfo { identifier, decimal_separator: '.' }
+}
+
+/// Scale bytes to the largest unit below `bytes` for the binary/decimal styles
+fn format_scaled(bytes: u64, suffix: &str, options: &FormatOptions) -> String {
+    let (base, units) = match options.units {
+        UnitStyle::Decimal => (1000.0, ["B", "kB", "MB", "GB", "TB"]),
+        _ => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB"]),
+    };
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B{}", bytes, suffix)
+    } else {
+        options.localize(format!("{:.1} {}{}", value, units[unit], suffix))
+    }
+}
+
+pub fn format_speed(bytes: u64, options: &FormatOptions) -> String {
+    if options.units != UnitStyle::Compact {
+        format_scaled(bytes, "/s", options)
+    } else if bytes < 1024 {
         format!("{:>3} B", bytes)
     } else if bytes < 1024 * 1024 {
         format!("{:>3} K/s", bytes / 1024)
     } else {
-        format!("{:>3.1} M/s", bytes as f64 / 1024.0 / 1024.0)
+        options.localize(format!("{:>3.1} M/s", bytes as f64 / 1024.0 / 1024.0))
     }
 }
 
-pub fn format_size(bytes: usize) -> String {
-    if bytes < 1024 {
+pub fn format_size(bytes: usize, options: &FormatOptions) -> String {
+    if options.units != UnitStyle::Compact {
+        format_scaled(bytes as u64, "", options)
+    } else if bytes < 1024 {
         format!("{} B", bytes)
     } else if bytes < 1024 * 1024 {
-        format!("{:.1} KB", bytes as f64 / 1024.0)
+        options.localize(format!("{:.1} KB", bytes as f64 / 1024.0))
     } else {
-        format!("{:.1} MB", bytes as f64 / 1024.0 / 1024.0)
+        options.localize(format!("{:.1} MB", bytes as f64 / 1024.0 / 1024.0))
     }
 }
 
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs < 1.0 {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.1}s", secs)
+    }
+}
+
+/// Humanize an uptime as its two largest units, e.g. "3d 4h", "2h 15m", "42m"
+pub fn format_uptime(secs: u64) -> String {
+    let days = secs / 86_400;
+    let hours = secs % 86_400 / 3_600;
+    let minutes = secs % 3_600 / 60;
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+const BACKOFF_MAX_EXPONENT: u32 = 5;
+const BACKOFF_MAX_JITTER_MS: u32 = 250;
+
+/// Look up a dotted path like "data.link" or "files.0.url" in a JSON value;
+/// numeric segments index into arrays
+pub fn json_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |current, segment| match current {
+            serde_json::Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => current.get(segment),
+        })
+}
+
+/// Expand a leading `~/` to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => {
+            let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+            PathBuf::from(home).join(rest)
+        }
+        None => PathBuf::from(path),
+    }
+}
+
+/// Delay before retry number `attempt` (0-based): 1s, 2s, 4s, ... capped at 32s,
+/// plus up to 250ms of jitter so clients don't retry in lockstep
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let exponential = BACKOFF_BASE * 2u32.pow(attempt.min(BACKOFF_MAX_EXPONENT));
+    // Sub-second clock noise is random enough for jitter and avoids a rand dependency
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    exponential + Duration::from_millis((nanos % (BACKOFF_MAX_JITTER_MS + 1)) as u64)
+}
+
+/// Longest server-requested wait honored; longer values are clamped
+const RETRY_AFTER_MAX: Duration = Duration::from_secs(120);
+
+/// Parse a `Retry-After` header: delay in seconds or an HTTP date, capped at
+/// RETRY_AFTER_MAX. A date in the past means retry now.
+pub fn retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(secs) => Duration::from_secs(secs),
+        Err(_) => {
+            let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or(Duration::ZERO)
+        }
+    };
+    Some(delay.min(RETRY_AFTER_MAX))
+}
+
+/// Exponential moving average step; the first sample seeds the average
+pub fn ema(previous: Option<f64>, value: f64, alpha: f64) -> f64 {
+    match previous {
+        Some(prev) => alpha * value + (1.0 - alpha) * prev,
+        None => value,
+    }
+}
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Render samples as Unicode block characters, clamping values to 0..=max
+pub fn sparkline(samples: &[f32], max: f32) -> String {
+    samples
+        .iter()
+        .map(|&value| {
+            let ratio = if max > 0.0 { (value / max).clamp(0.0, 1.0) } else { 0.0 };
+            let index = (ratio * (SPARK_CHARS.len() - 1) as f32).round() as usize;
+            SPARK_CHARS[index]
+        })
+        .collect()
+}
+
+pub const CPU_WARN_PERCENT: f32 = 50.0;
+pub const CPU_CRITICAL_PERCENT: f32 = 80.0;
+
+#[cfg(target_os = "macos")]
 pub fn get_cpu_color(cpu: f32) -> Retained<NSColor> {
-    if cpu >= 80.0 {
+    if cpu >= CPU_CRITICAL_PERCENT {
         NSColor::yellowColor()
-    } else if cpu >= 50.0 {
+    } else if cpu >= CPU_WARN_PERCENT {
         NSColor::orangeColor()
     } else {
         NSColor::controlTextColor()
     }
 }
 
-pub fn get_network_color(bytes_per_sec: u64) -> Retained<NSColor> {
-    let mb_per_sec = bytes_per_sec as f64 / (1024.0 * 1024.0);
-    if mb_per_sec > 10.0 {
+/// Whether the CPU is at or above the `tray.cpu_temp_critical` threshold (°C)
+pub fn cpu_over_temperature(temp: Option<f32>, critical: f32) -> bool {
+    temp.is_some_and(|temp| critical > 0.0 && temp >= critical)
+}
+
+/// CPU segment color: red when running hot, whatever the usage, so throttling
+/// shows up even when idle; usage-based otherwise or without a temperature reading
+#[cfg(target_os = "macos")]
+pub fn cpu_segment_color(usage: f32, temp: Option<f32>, temp_critical: f32) -> Retained<NSColor> {
+    if cpu_over_temperature(temp, temp_critical) {
+        NSColor::redColor()
+    } else {
+        get_cpu_color(usage)
+    }
+}
+
+/// Colored dot for the `{icon}` token, using the same thresholds as get_cpu_color
+pub fn cpu_icon(cpu: f32) -> &'static str {
+    if cpu >= CPU_CRITICAL_PERCENT {
+        "🔴"
+    } else if cpu >= CPU_WARN_PERCENT {
+        "🟡"
+    } else {
+        "🟢"
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn get_memory_color(percent: f32, warn_percent: f32, critical_percent: f32) -> Retained<NSColor> {
+    if percent >= critical_percent {
         NSColor::redColor()
-    } else if mb_per_sec >= 5.0 {
+    } else if percent >= warn_percent {
         NSColor::orangeColor()
     } else {
         NSColor::controlTextColor()
     }
 }
+
+/// Threshold band a reading falls in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub enum Severity {
+    Normal,
+    Warn,
+    Critical,
+}
+
+/// Network speed band against the `tray.network` thresholds (MB/s)
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub fn network_severity(bytes_per_sec: u64, warn_mbps: f64, critical_mbps: f64) -> Severity {
+    let mb_per_sec = bytes_per_sec as f64 / (1024.0 * 1024.0);
+    if mb_per_sec > critical_mbps {
+        Severity::Critical
+    } else if mb_per_sec >= warn_mbps {
+        Severity::Warn
+    } else {
+        Severity::Normal
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn get_network_color(bytes_per_sec: u64, warn_mbps: f64, critical_mbps: f64) -> Retained<NSColor> {
+    match network_severity(bytes_per_sec, warn_mbps, critical_mbps) {
+        Severity::Critical => NSColor::redColor(),
+        Severity::Warn => NSColor::orangeColor(),
+        Severity::Normal => NSColor::controlTextColor(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn network_severity_boundaries() {
+        assert_eq!(network_severity(5 * MB - 1, 5.0, 10.0), Severity::Normal);
+        // Exactly warn_mbps is orange
+        assert_eq!(network_severity(5 * MB, 5.0, 10.0), Severity::Warn);
+        // Exactly critical_mbps is still orange; anything above is red
+        assert_eq!(network_severity(10 * MB, 5.0, 10.0), Severity::Warn);
+        assert_eq!(network_severity(10 * MB + 1, 5.0, 10.0), Severity::Critical);
+    }
+
+    #[test]
+    fn ema_first_sample_seeds_average() {
+        assert_eq!(ema(None, 42.0, 0.5), 42.0);
+    }
+
+    #[test]
+    fn ema_steps_toward_new_sample() {
+        assert_eq!(ema(Some(100.0), 200.0, 0.25), 125.0);
+        let smoothed = [10.0, 20.0, 40.0].into_iter().fold(None, |avg, v| Some(ema(avg, v, 0.5)));
+        assert_eq!(smoothed, Some(27.5));
+    }
+
+    fn options(units: UnitStyle) -> FormatOptions {
+        FormatOptions { decimal_separator: '.', units }
+    }
+
+    #[test]
+    fn format_binary_boundaries() {
+        let binary = options(UnitStyle::Binary);
+        assert_eq!(format_size(999, &binary), "999 B");
+        assert_eq!(format_size(1000, &binary), "1000 B");
+        assert_eq!(format_size(1023, &binary), "1023 B");
+        assert_eq!(format_size(1024, &binary), "1.0 KiB");
+        assert_eq!(format_speed(1023, &binary), "1023 B/s");
+        assert_eq!(format_speed(1024, &binary), "1.0 KiB/s");
+    }
+
+    #[test]
+    fn format_decimal_boundaries() {
+        let decimal = options(UnitStyle::Decimal);
+        assert_eq!(format_size(999, &decimal), "999 B");
+        assert_eq!(format_size(1000, &decimal), "1.0 kB");
+        assert_eq!(format_size(1023, &decimal), "1.0 kB");
+        assert_eq!(format_size(1024, &decimal), "1.0 kB");
+        assert_eq!(format_speed(999, &decimal), "999 B/s");
+        assert_eq!(format_speed(1000, &decimal), "1.0 kB/s");
+    }
+
+    #[test]
+    fn format_compact_unchanged() {
+        let compact = FormatOptions::default();
+        assert_eq!(format_size(999, &compact), "999 B");
+        assert_eq!(format_size(1000, &compact), "1000 B");
+        assert_eq!(format_size(1023, &compact), "1023 B");
+        assert_eq!(format_size(1024, &compact), "1.0 KB");
+        assert_eq!(format_speed(999, &compact), "999 B");
+        assert_eq!(format_speed(1000, &compact), "1000 B");
+        assert_eq!(format_speed(1023, &compact), "1023 B");
+        assert_eq!(format_speed(1024, &compact), "  1 K/s");
+    }
+
+    #[test]
+    fn backoff_delay_schedule() {
+        for attempt in 0..=7 {
+            let base = Duration::from_secs(2u64.pow(attempt.min(5)));
+            let delay = backoff_delay(attempt);
+            assert!(delay >= base && delay <= base + Duration::from_millis(250), "attempt {}: {:?}", attempt, delay);
+        }
+    }
+
+    #[test]
+    fn lock_or_recover_survives_poisoning() {
+        let lock = std::sync::Arc::new(Mutex::new(7));
+        let poisoner = lock.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("poison the lock");
+        })
+        .join();
+
+        assert!(lock.is_poisoned());
+        assert_eq!(*lock.lock_or_recover(), 7);
+    }
+}
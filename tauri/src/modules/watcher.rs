@@ -0,0 +1,81 @@
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::time::Duration;
+use notify::{RecursiveMode, Watcher};
+use tauri::{AppHandle, Emitter, Manager};
+use crate::modules::AppState;
+use crate::modules::config::{get_config_path, load_config};
+use crate::modules::tray::effective_display_mode;
+use crate::modules::utils::LockExt;
+
+/// Editors often write a file more than once per save; wait for this much quiet first
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watch config.toml and reload on change, emitting `config-reloaded`
+pub fn start_config_watcher(app: AppHandle) {
+    let config_path = get_config_path();
+    let Some(config_dir) = config_path.parent().map(|dir| dir.to_path_buf()) else {
+        return;
+    };
+    let Some(config_file) = config_path.file_name().map(|name| name.to_os_string()) else {
+        return;
+    };
+
+    std::thread::spawn(move || {
+        let (tx, rx) = mpsc::channel();
+
+        // Watch the directory: editors that save via rename replace the file's inode
+        let mut watcher = match notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if event.paths.iter().any(|p| p.file_name() == Some(config_file.as_os_str())) {
+                    let _ = tx.send(());
+                }
+            }
+        }) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                log::error!("Failed to create config watcher: {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = std::fs::create_dir_all(&config_dir) {
+            log::warn!("Failed to create config directory {:?}: {}", config_dir, e);
+        }
+        if let Err(e) = watcher.watch(&config_dir, RecursiveMode::NonRecursive) {
+            log::error!("Failed to watch {:?}: {}", config_dir, e);
+            return;
+        }
+        log::info!("Watching {:?} for changes", config_path);
+
+        while rx.recv().is_ok() {
+            // Debounce: swallow follow-up events until the file has been quiet
+            while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+            log::info!("Config file changed, reloading");
+            reload(&app);
+        }
+    });
+}
+
+fn reload(app: &AppHandle) {
+    let config = load_config();
+    let state = app.state::<AppState>();
+
+    {
+        let mut label = state.tray_label.lock_or_recover();
+        label.prefix = config.tray.prefix.clone();
+        label.suffix = config.tray.suffix.clone();
+    }
+    *state.display_mode.lock_or_recover() = effective_display_mode(config.tray.display_mode);
+    *state.disk_mount.lock_or_recover() = config.disk.mount_point.clone();
+    state.config_reloaded.store(true, Ordering::Relaxed);
+
+    crate::modules::upload::reset_upload_client();
+
+    if let Err(e) = crate::modules::mijia::reload_mijia_config() {
+        log::info!("Mijia config not reloaded: {}", e);
+    }
+
+    let _ = app.emit("config-reloaded", ());
+}
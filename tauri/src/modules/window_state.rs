@@ -0,0 +1,128 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewWindow};
+use crate::modules::AppState;
+use crate::modules::config::{get_config_dir, load_config};
+
+/// Main window geometry in physical pixels, saved when the window is hidden
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+struct WindowGeometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+/// Window state file: stored next to config.toml
+fn get_window_state_path() -> PathBuf {
+    get_config_dir().join("window_state.json")
+}
+
+fn load_geometry() -> Option<WindowGeometry> {
+    let contents = fs::read_to_string(get_window_state_path()).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn remember_enabled() -> bool {
+    !load_config().general.fixed_window_position
+}
+
+/// Save the window's position and size, unless `general.fixed_window_position` is set
+pub fn save_window_state(window: &WebviewWindow) {
+    if !remember_enabled() {
+        return;
+    }
+    let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+        return;
+    };
+    let geometry = WindowGeometry { x: position.x, y: position.y, width: size.width, height: size.height };
+
+    let path = get_window_state_path();
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    match serde_json::to_string(&geometry) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                log::error!("Failed to save window state: {}", e);
+            }
+        }
+        Err(e) => log::error!("Failed to encode window state: {}", e),
+    }
+}
+
+fn contains(monitor: &Monitor, x: i32, y: i32) -> bool {
+    let origin = monitor.position();
+    let size = monitor.size();
+    x >= origin.x && y >= origin.y && x < origin.x + size.width as i32 && y < origin.y + size.height as i32
+}
+
+/// Keep the saved geometry on a connected display: if its top-left corner is
+/// off every monitor (e.g. an external display was unplugged), pull it onto
+/// the primary one
+fn clamp_to_display(window: &WebviewWindow, geometry: WindowGeometry) -> WindowGeometry {
+    let monitors = window.available_monitors().unwrap_or_default();
+    if monitors.iter().any(|m| contains(m, geometry.x, geometry.y)) {
+        return geometry;
+    }
+    let Some(monitor) = window.primary_monitor().ok().flatten().or_else(|| monitors.into_iter().next()) else {
+        return geometry;
+    };
+
+    let origin = monitor.position();
+    let size = monitor.size();
+    let width = geometry.width.min(size.width);
+    let height = geometry.height.min(size.height);
+    WindowGeometry {
+        x: geometry.x.clamp(origin.x, origin.x + (size.width - width) as i32),
+        y: geometry.y.clamp(origin.y, origin.y + (size.height - height) as i32),
+        width,
+        height,
+    }
+}
+
+fn restore_window_state(window: &WebviewWindow) {
+    if !remember_enabled() {
+        return;
+    }
+    let Some(geometry) = load_geometry() else {
+        return;
+    };
+    let geometry = clamp_to_display(window, geometry);
+    let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+    let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+}
+
+/// Show and focus the main window at its saved position
+pub fn show_main_window(app: &AppHandle) -> Option<WebviewWindow> {
+    let window = app.get_webview_window("main")?;
+    if !window.is_visible().unwrap_or(false) {
+        restore_window_state(&window);
+    }
+    let _ = window.show();
+    let _ = window.set_focus();
+    app.state::<AppState>().window_visible.store(true, Ordering::Relaxed);
+    Some(window)
+}
+
+/// Save the geometry and hide; the app keeps running in the menu bar
+pub fn hide_main_window(window: &WebviewWindow) {
+    save_window_state(window);
+    let _ = window.hide();
+    window.state::<AppState>().window_visible.store(false, Ordering::Relaxed);
+}
+
+/// Hide the main window if it's visible and focused, otherwise bring it up,
+/// like clicking the tray icon
+pub fn toggle_main_window(app: &AppHandle) {
+    match app.get_webview_window("main") {
+        Some(window) if window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false) => {
+            hide_main_window(&window);
+        }
+        _ => {
+            show_main_window(app);
+        }
+    }
+}